@@ -1,6 +1,6 @@
 use node_template_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY, Balance, GomokuConfig, PoolConfig
+	SystemConfig, WASM_BINARY, Balance, GomokuConfig, PoolConfig, Perbill
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -133,7 +133,7 @@ fn testnet_genesis(
 
 	// Gomoku config
 	const OPEN_FEE: Balance = 10000000000000000;
-	const MAX_GOMOKU_PLAYER: u8 = 2u8;
+	const HOUSE_RAKE: Perbill = Perbill::from_percent(1);
 
 	// Pool config
 	const POOL_FEE: Balance = 10000000000000000;
@@ -162,7 +162,9 @@ fn testnet_genesis(
 		transaction_payment: Default::default(),
 		gomoku: GomokuConfig {
 			open_fee: OPEN_FEE,
-			max_gomoku_player: MAX_GOMOKU_PLAYER,
+			house_rake: HOUSE_RAKE,
+			fee_recipient: None,
+			board_limits: Default::default(),
 		},
 		pool: PoolConfig {
 			mark_block: MARK_BLOCK,
@@ -5,14 +5,28 @@
 
 #![warn(missing_docs)]
 
-use std::sync::Arc;
+use std::{marker::PhantomData, sync::Arc};
 
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use codec::Decode;
+use futures::{future, stream, FutureExt, SinkExt, StreamExt};
+use jsonrpc_core::{Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use node_template_runtime::{
+	opaque::Block, pallet_gomoku, AccountId, Balance, BlockNumber, Event, Hash, Index, Runtime,
+};
+use pallet_gomoku::{board::Board, GameOutcome, GameStatus};
+use pallet_gomoku_rpc::GomokuRuntimeApi;
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
+use serde::Serialize;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_core::{hashing::twox_128, storage::StorageKey, H256};
+use sp_runtime::generic::BlockId;
 
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
@@ -22,29 +36,42 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor driving the pub-sub subscriptions.
+	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+pub fn create_full<C, P, B>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
 where
+	B: Backend<Block> + Send + Sync + 'static,
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, B>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: GomokuRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 {
+	use pallet_gomoku_rpc::{Gomoku, GomokuApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, subscription_executor } = deps;
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 
+	io.extend_with(GomokuApi::to_delegate(Gomoku::new(client.clone())));
+
+	io.extend_with(GomokuSubscribeApi::to_delegate(GomokuSubscription::new(
+		client.clone(),
+		subscription_executor,
+	)));
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -52,3 +79,422 @@ where
 
 	io
 }
+
+/// An update of a subscribed gomoku game, found in the events of `block`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum GameNotification {
+	/// A player took a seat at the open game.
+	PlayerJoined {
+		/// Block the event was found in.
+		block: Hash,
+		/// The joining player.
+		player: AccountId,
+	},
+	/// A stone was placed on (x, y).
+	Move {
+		/// Block the event was found in.
+		block: Hash,
+		/// The moving player.
+		player: AccountId,
+		/// Column of the stone.
+		x: u8,
+		/// Row of the stone.
+		y: u8,
+	},
+	/// A player left or missed a deadline and is out of the game.
+	PlayerOut {
+		/// Block the event was found in.
+		block: Hash,
+		/// The player out of the game.
+		player: AccountId,
+	},
+	/// The board after `block` and the player to move next.
+	Turn {
+		/// Block the board was read at.
+		block: Hash,
+		/// The player to move.
+		player: AccountId,
+		/// Last block the player can move in.
+		deadline: BlockNumber,
+		/// The board as of `block`.
+		board: Board,
+	},
+	/// The game ended, this is the last notification.
+	Ended {
+		/// Block the event was found in.
+		block: Hash,
+		/// How the game ended.
+		outcome: GameOutcome<AccountId>,
+		/// Total paid out to the players.
+		paid: Balance,
+		/// Share of the pot taken by the house.
+		rake: Balance,
+		/// First block the ended game may be pruned in, read it before then.
+		prunable_at: BlockNumber,
+	},
+	/// The game was cancelled before it started, this is the last notification.
+	Cancelled {
+		/// Block the event was found in.
+		block: Hash,
+	},
+}
+
+impl GameNotification {
+	/// Whether nothing follows this notification.
+	pub fn is_final(&self) -> bool {
+		matches!(self, Self::Ended { .. } | Self::Cancelled { .. })
+	}
+}
+
+/// Gomoku pub-sub RPC methods.
+#[rpc]
+pub trait GomokuSubscribeApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Stream the moves, turns and end of an open or running game. Best blocks are followed
+	/// by default, finalized blocks when `finalized` is set, every block in between is read.
+	/// The stream closes once the game is no longer open or running.
+	#[pubsub(subscription = "gomoku_game", subscribe, name = "gomoku_subscribeGame")]
+	fn subscribe_game(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<GameNotification>,
+		game_id: H256,
+		finalized: Option<bool>,
+	);
+
+	/// Stop streaming a game.
+	#[pubsub(subscription = "gomoku_game", unsubscribe, name = "gomoku_unsubscribeGame")]
+	fn unsubscribe_game(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+}
+
+/// Pushes gomoku game updates to subscribers as blocks come in.
+pub struct GomokuSubscription<C, B> {
+	client: Arc<C>,
+	manager: SubscriptionManager,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> GomokuSubscription<C, B> {
+	/// Create the subscription handler, driven by `executor`.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, manager: SubscriptionManager::new(Arc::new(executor)), _marker: PhantomData }
+	}
+}
+
+/// Storage key of `frame_system::Events`.
+fn events_key() -> StorageKey {
+	StorageKey([twox_128(b"System"), twox_128(b"Events")].concat())
+}
+
+/// The updates of `game_id` among the gomoku `events` of `block`, and whether its turn changed.
+/// Nothing after the game's end is kept.
+fn game_notifications(
+	events: impl IntoIterator<Item = pallet_gomoku::Event<Runtime>>,
+	block: Hash,
+	game_id: [u8; 32],
+) -> (Vec<GameNotification>, bool) {
+	let mut notifications = Vec::new();
+	let mut turn_changed = false;
+	for event in events {
+		match event {
+			pallet_gomoku::Event::PlayerJoinGame(player, id) if id == game_id =>
+				notifications.push(GameNotification::PlayerJoined { block, player }),
+			pallet_gomoku::Event::MovePlayed(id, player, x, y) if id == game_id => {
+				turn_changed = true;
+				notifications.push(GameNotification::Move { block, player, x, y });
+			},
+			pallet_gomoku::Event::PlayerLeft(id, player) |
+			pallet_gomoku::Event::PlayerTimedOut(id, player)
+				if id == game_id =>
+			{
+				turn_changed = true;
+				notifications.push(GameNotification::PlayerOut { block, player });
+			},
+			pallet_gomoku::Event::GameStarted(id, _) |
+			pallet_gomoku::Event::ColorChosen(id, _, _)
+				if id == game_id =>
+				turn_changed = true,
+			pallet_gomoku::Event::GameEnded(id, outcome, _, paid, rake, prunable_at)
+				if id == game_id =>
+			{
+				notifications.push(GameNotification::Ended {
+					block,
+					outcome,
+					paid,
+					rake,
+					prunable_at,
+				});
+				return (notifications, false)
+			},
+			pallet_gomoku::Event::GameCancelled(id) if id == game_id => {
+				notifications.push(GameNotification::Cancelled { block });
+				return (notifications, false)
+			},
+			_ => (),
+		}
+	}
+	(notifications, turn_changed)
+}
+
+impl<C, B> GomokuSubscription<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, B>,
+	C: HeaderMetadata<Block, Error = BlockChainError>,
+	C::Api: GomokuRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+{
+	/// The game's updates in `block`, read from its events.
+	fn notifications(client: &C, block: Hash, game_id: [u8; 32]) -> Vec<GameNotification> {
+		let (mut notifications, turn_changed) =
+			game_notifications(Self::gomoku_events(client, block), block, game_id);
+		if turn_changed {
+			notifications.extend(Self::turn(client, block, game_id));
+		}
+		notifications
+	}
+
+	/// The game's updates in every block enacted after `from` up to `to`. `None` follows them
+	/// once the game is no longer open or running.
+	fn updates(
+		client: &C,
+		from: Hash,
+		to: Hash,
+		game_id: [u8; 32],
+	) -> Vec<Option<GameNotification>> {
+		// block notifications may skip blocks, the route from the last one seen covers them
+		let blocks = match sp_blockchain::tree_route(client, from, to) {
+			Ok(route) => route.enacted().iter().map(|block| block.hash).collect(),
+			Err(e) => {
+				log::warn!("Unable to walk from block {:?} to {:?}: {:?}", from, to, e);
+				vec![to]
+			},
+		};
+		let mut updates = Vec::new();
+		for block in blocks {
+			let notifications = Self::notifications(client, block, game_id);
+			let ended = notifications.iter().any(GameNotification::is_final);
+			updates.extend(notifications.into_iter().map(Some));
+			if ended {
+				updates.push(None);
+				return updates
+			}
+		}
+		// the end may have been missed, e.g. in blocks retracted by a reorg
+		match Self::is_live(client, to, game_id) {
+			Ok(true) => (),
+			Ok(false) => updates.push(None),
+			Err(e) => log::warn!("Unable to read game at block {:?}: {:?}", to, e),
+		}
+		updates
+	}
+
+	/// Whether the game is open or running as of `block`.
+	fn is_live(client: &C, block: Hash, game_id: [u8; 32]) -> Result<bool, sp_api::ApiError> {
+		let game = client.runtime_api().game(&BlockId::Hash(block), game_id)?;
+		Ok(matches!(game.map(|game| game.status), Some(GameStatus::Open | GameStatus::Start)))
+	}
+
+	/// The gomoku events deposited in `block`.
+	fn gomoku_events(client: &C, block: Hash) -> Vec<pallet_gomoku::Event<Runtime>> {
+		let records = match client.storage(&BlockId::Hash(block), &events_key()) {
+			Ok(Some(data)) =>
+				Vec::<frame_system::EventRecord<Event, Hash>>::decode(&mut &data.0[..])
+					.map_err(|e| log::warn!("Undecodable events in block {:?}: {:?}", block, e))
+					.unwrap_or_default(),
+			Ok(None) => Vec::new(),
+			Err(e) => {
+				log::warn!("Unable to read events of block {:?}: {:?}", block, e);
+				Vec::new()
+			},
+		};
+		records
+			.into_iter()
+			.filter_map(|record| match record.event {
+				Event::Gomoku(event) => Some(event),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// The player to move and the board as of `block`, if the game is running.
+	fn turn(client: &C, block: Hash, game_id: [u8; 32]) -> Option<GameNotification> {
+		let api = client.runtime_api();
+		let at = BlockId::Hash(block);
+		let (player, deadline) = api.current_turn(&at, game_id).ok()??;
+		let board = api.board(&at, game_id).ok()??;
+		Some(GameNotification::Turn { block, player, deadline, board })
+	}
+}
+
+impl<C, B> GomokuSubscribeApi for GomokuSubscription<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, B>,
+	C: HeaderMetadata<Block, Error = BlockChainError>,
+	C: BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: GomokuRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_game(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<GameNotification>,
+		game_id: H256,
+		finalized: Option<bool>,
+	) {
+		let game_id = game_id.to_fixed_bytes();
+		let finalized = finalized.unwrap_or(false);
+		let info = self.client.info();
+		let start = if finalized { info.finalized_hash } else { info.best_hash };
+		match Self::is_live(&self.client, start, game_id) {
+			Ok(true) => (),
+			Ok(false) => {
+				let _ = subscriber.reject(RpcError {
+					code: ErrorCode::InvalidParams,
+					message: "Game is not open or running.".into(),
+					data: None,
+				});
+				return
+			},
+			Err(e) => {
+				let _ = subscriber.reject(RpcError {
+					code: ErrorCode::ServerError(pallet_gomoku_rpc::Error::RuntimeError.into()),
+					message: "Unable to query gomoku games.".into(),
+					data: Some(e.to_string().into()),
+				});
+				return
+			},
+		}
+
+		// imported blocks may be retracted, only finalized ones are sure to stay
+		let blocks = if finalized {
+			self.client.finality_notification_stream().map(|block| block.hash).boxed()
+		} else {
+			self.client
+				.import_notification_stream()
+				.filter(|block| future::ready(block.is_new_best))
+				.map(|block| block.hash)
+				.boxed()
+		};
+		let initial = Self::turn(&self.client, start, game_id);
+		let client = self.client.clone();
+
+		self.manager.add(subscriber, move |sink| {
+			let updates = blocks
+				.scan(start, move |last, block| {
+					let updates = Self::updates(&client, *last, block, game_id);
+					*last = block;
+					future::ready(Some(stream::iter(updates)))
+				})
+				.flatten()
+				.take_while(|update| future::ready(update.is_some()))
+				.filter_map(future::ready);
+
+			stream::iter(initial)
+				.chain(updates)
+				.map(|notification| Ok(Ok(notification)))
+				.forward(
+					sink.sink_map_err(|e| {
+						log::warn!("Error sending gomoku notifications: {:?}", e)
+					}),
+				)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_game(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type GomokuEvent = pallet_gomoku::Event<Runtime>;
+
+	const GAME: [u8; 32] = [1u8; 32];
+	const OTHER_GAME: [u8; 32] = [2u8; 32];
+
+	fn account(seed: u8) -> AccountId {
+		AccountId::new([seed; 32])
+	}
+
+	#[test]
+	fn game_notifications_should_keep_the_game_events() {
+		let block = Hash::repeat_byte(7);
+		let events = vec![
+			GomokuEvent::PlayerJoinGame(account(1), GAME),
+			GomokuEvent::PlayerJoinGame(account(2), OTHER_GAME),
+			GomokuEvent::MovePlayed(OTHER_GAME, account(2), 3, 3),
+			GomokuEvent::MovePlayed(GAME, account(1), 7, 7),
+			GomokuEvent::PlayerTimedOut(GAME, account(3)),
+		];
+
+		assert_eq!(
+			game_notifications(events, block, GAME),
+			(
+				vec![
+					GameNotification::PlayerJoined { block, player: account(1) },
+					GameNotification::Move { block, player: account(1), x: 7, y: 7 },
+					GameNotification::PlayerOut { block, player: account(3) },
+				],
+				true
+			)
+		);
+	}
+
+	#[test]
+	fn game_notifications_should_report_turn_changes() {
+		let block = Hash::repeat_byte(7);
+		let started = vec![GomokuEvent::GameStarted(GAME, Default::default())];
+		assert_eq!(game_notifications(started, block, GAME), (vec![], true));
+
+		let other = vec![GomokuEvent::GameStarted(OTHER_GAME, Default::default())];
+		assert_eq!(game_notifications(other, block, GAME), (vec![], false));
+	}
+
+	#[test]
+	fn game_notifications_should_stop_at_the_end() {
+		let block = Hash::repeat_byte(7);
+		let events = vec![
+			GomokuEvent::MovePlayed(GAME, account(1), 7, 7),
+			GomokuEvent::GameEnded(GAME, GameOutcome::Win(account(1)), Some(account(1)), 20, 1, 61),
+			GomokuEvent::PlayerJoinGame(account(2), GAME),
+		];
+		let (notifications, turn_changed) = game_notifications(events, block, GAME);
+		assert_eq!(
+			notifications,
+			vec![
+				GameNotification::Move { block, player: account(1), x: 7, y: 7 },
+				GameNotification::Ended {
+					block,
+					outcome: GameOutcome::Win(account(1)),
+					paid: 20,
+					rake: 1,
+					prunable_at: 61,
+				},
+			]
+		);
+		assert!(!turn_changed, "no turn follows the end");
+		assert!(notifications.last().unwrap().is_final());
+
+		let cancelled = vec![GomokuEvent::GameCancelled(GAME)];
+		assert_eq!(
+			game_notifications(cancelled, block, GAME),
+			(vec![GameNotification::Cancelled { block }], false)
+		);
+	}
+}
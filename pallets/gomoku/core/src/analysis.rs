@@ -0,0 +1,106 @@
+//! Line analysis around a move, for rule sets that need more than counting stones in a row.
+//!
+//! A move is looked at through the four lines crossing it. Each [`Line`] holds the cells
+//! within [`REACH`] of the move as seen by the player making it, which is enough to tell
+//! fives from overlines and to find fours and threes.
+
+use alloc::vec::Vec;
+
+use crate::board::{Board, EMPTY};
+
+/// Cells looked at on each side of the move.
+pub const REACH: usize = 5;
+
+/// Stones in a row that make a five.
+pub const FIVE: usize = 5;
+
+const CENTER: usize = REACH;
+const LEN: usize = 2 * REACH + 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+	Own,
+	Empty,
+	/// An opponent stone or the edge of the board.
+	Blocked,
+}
+
+/// One line through a move, with the move already placed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Line {
+	cells: [Cell; LEN],
+}
+
+impl Line {
+	/// The line through (x, y) along (dx, dy) once `player` has a stone on (x, y).
+	pub fn through(board: &Board, player: i8, x: usize, y: usize, dx: i32, dy: i32) -> Self {
+		let mut cells = [Cell::Blocked; LEN];
+		for (i, cell) in cells.iter_mut().enumerate() {
+			let step = i as i32 - CENTER as i32;
+			let (cx, cy) = (x as i32 + step * dx, y as i32 + step * dy);
+			if cx < 0 || cy < 0 {
+				continue
+			}
+			*cell = match board.get(cx as usize, cy as usize) {
+				Some(stone) if stone == player => Cell::Own,
+				Some(EMPTY) => Cell::Empty,
+				_ => Cell::Blocked,
+			};
+		}
+		cells[CENTER] = Cell::Own;
+		Line { cells }
+	}
+
+	/// Length of the run of own stones through the move. A run reaching the end of the line
+	/// is longer than five, so it is never mistaken for one.
+	pub fn run_length(&self) -> usize {
+		let own = |cell: &&Cell| **cell == Cell::Own;
+		let before = self.cells[..CENTER].iter().rev().take_while(own).count();
+		let after = self.cells[CENTER + 1..].iter().take_while(own).count();
+		before + 1 + after
+	}
+
+	pub fn is_five(&self) -> bool {
+		self.run_length() == FIVE
+	}
+
+	pub fn is_overline(&self) -> bool {
+		self.run_length() > FIVE
+	}
+
+	/// Empty cells where one more stone makes exactly five through the move.
+	pub fn five_points(&self) -> Vec<usize> {
+		(1..LEN - 1)
+			.filter(|&i| self.cells[i] == Cell::Empty && self.with_own(i).is_five())
+			.collect()
+	}
+
+	/// Four stones in a row with both ends open, so it cannot be stopped.
+	pub fn is_straight_four(&self) -> bool {
+		let points = self.five_points();
+		points.len() == 2 && points[1] - points[0] == FIVE
+	}
+
+	/// Number of fours the move makes on this line. Both ends of a straight four count as
+	/// a single four, two fives on either side of the move count as two.
+	pub fn fours(&self) -> usize {
+		if self.is_straight_four() {
+			return 1
+		}
+		self.five_points().len()
+	}
+
+	/// Whether one more stone turns this line into a straight four. Whether that stone would
+	/// itself be allowed is not looked at.
+	pub fn is_three(&self) -> bool {
+		self.fours() == 0 &&
+			(1..LEN - 1)
+				.any(|i| self.cells[i] == Cell::Empty && self.with_own(i).is_straight_four())
+	}
+
+	fn with_own(&self, i: usize) -> Self {
+		let mut line = *self;
+		line.cells[i] = Cell::Own;
+		line
+	}
+}
@@ -0,0 +1,205 @@
+//! The gomoku board and the line counting the win check is built on.
+
+use alloc::{vec, vec::Vec};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Value of an empty cell, a stone is the index of the player who placed it.
+pub const EMPTY: i8 = -1;
+
+/// Largest board side a game can be opened with.
+pub const MAX_BOARD_SIZE: u8 = 19;
+
+/// Steps of the four lines through a cell: vertical, horizontal and both diagonals.
+pub const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Bits a cell takes while the stones on the board fit in them: empty and three players.
+const NARROW_BITS: u8 = 2;
+
+/// Bits a cell takes once a stone no longer fits in `NARROW_BITS`.
+const WIDE_BITS: u8 = 4;
+
+/// Cells are packed `8 / bits` to a byte, so a 15x15 two player board takes 57 bytes.
+#[derive(Clone, Encode, Decode, Debug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(into = "BoardCells", from = "BoardCells"))]
+pub struct Board {
+	pub width: u8,
+	pub height: u8,
+	/// `NARROW_BITS` until a fourth player places a stone.
+	bits: u8,
+	/// Each cell holds 0 when empty, the stone plus one otherwise.
+	cells: Vec<u8>,
+}
+
+/// The board as clients see it, every cell unpacked in the order of the packed cells.
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize)]
+struct BoardCells {
+	width: u8,
+	height: u8,
+	cells: Vec<i8>,
+}
+
+#[cfg(feature = "std")]
+impl From<Board> for BoardCells {
+	fn from(board: Board) -> Self {
+		let cells = (0..board.len()).filter_map(|index| board.read(index)).map(stone_of).collect();
+		BoardCells { width: board.width, height: board.height, cells }
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<BoardCells> for Board {
+	fn from(view: BoardCells) -> Self {
+		let mut board = Board::new(view.width, view.height);
+		for (index, stone) in view.cells.into_iter().enumerate().take(board.len()) {
+			board.write_stone(index, stone);
+		}
+		board
+	}
+}
+
+/// Boards are equal when they hold the same stones, however wide their cells are packed.
+impl PartialEq for Board {
+	fn eq(&self, other: &Self) -> bool {
+		self.width == other.width &&
+			self.height == other.height &&
+			(0..self.len()).all(|index| self.read(index) == other.read(index))
+	}
+}
+
+fn stone_of(value: u8) -> i8 {
+	value as i8 - 1
+}
+
+fn packed_len(cells: usize, bits: u8) -> usize {
+	let per_byte = (8 / bits) as usize;
+	(cells + per_byte - 1) / per_byte
+}
+
+impl Board {
+	pub fn new(width: u8, height: u8) -> Self {
+		let cells = vec![0; packed_len(width as usize * height as usize, NARROW_BITS)];
+		Board { width, height, bits: NARROW_BITS, cells }
+	}
+
+	pub fn contains(&self, x: usize, y: usize) -> bool {
+		x < self.width as usize && y < self.height as usize
+	}
+
+	/// The cell at (x, y), `None` outside of the board.
+	pub fn get(&self, x: usize, y: usize) -> Option<i8> {
+		if !self.contains(x, y) {
+			return None
+		}
+		self.read(x * self.height as usize + y).map(stone_of)
+	}
+
+	/// Place `stone` at (x, y), returns false outside of the board or for a value that is
+	/// neither `EMPTY` nor a player index.
+	pub fn set(&mut self, x: usize, y: usize, stone: i8) -> bool {
+		if !self.contains(x, y) {
+			return false
+		}
+		self.write_stone(x * self.height as usize + y, stone)
+	}
+
+	pub fn is_empty_at(&self, x: usize, y: usize) -> bool {
+		self.get(x, y) == Some(EMPTY)
+	}
+
+	pub fn is_full(&self) -> bool {
+		(0..self.len()).all(|index| self.read(index).map_or(false, |value| value != 0))
+	}
+
+	fn len(&self) -> usize {
+		self.width as usize * self.height as usize
+	}
+
+	/// Bits per cell, a corrupted width reads as `NARROW_BITS` rather than panicking.
+	fn cell_bits(&self) -> u8 {
+		if self.bits == WIDE_BITS {
+			WIDE_BITS
+		} else {
+			NARROW_BITS
+		}
+	}
+
+	/// The packed value of the cell at `index`.
+	fn read(&self, index: usize) -> Option<u8> {
+		let bits = self.cell_bits();
+		let per_byte = (8 / bits) as usize;
+		let byte = self.cells.get(index / per_byte)?;
+		let shift = (index % per_byte) as u8 * bits;
+		Some((byte >> shift) & ((1 << bits) - 1))
+	}
+
+	fn write(&mut self, index: usize, value: u8) -> bool {
+		let bits = self.cell_bits();
+		let per_byte = (8 / bits) as usize;
+		let shift = (index % per_byte) as u8 * bits;
+		let mask = ((1 << bits) - 1) << shift;
+		match self.cells.get_mut(index / per_byte) {
+			Some(byte) => {
+				*byte = (*byte & !mask) | (value << shift);
+				true
+			},
+			None => false,
+		}
+	}
+
+	fn write_stone(&mut self, index: usize, stone: i8) -> bool {
+		let value = match stone.checked_add(1).and_then(|value| u8::try_from(value).ok()) {
+			Some(value) if value < 1 << WIDE_BITS => value,
+			_ => return false,
+		};
+		if value >= 1 << self.cell_bits() {
+			self.widen(WIDE_BITS);
+		}
+		self.write(index, value)
+	}
+
+	/// Repack every cell `bits` wide.
+	fn widen(&mut self, bits: u8) {
+		let values: Vec<u8> =
+			(0..self.len()).map(|index| self.read(index).unwrap_or_default()).collect();
+		self.bits = bits;
+		self.cells = vec![0; packed_len(self.len(), bits)];
+		for (index, value) in values.into_iter().enumerate() {
+			self.write(index, value);
+		}
+	}
+
+	/// Stones of `player` in a row starting next to (x, y) and going along (dx, dy).
+	pub fn count_direction(&self, player: i8, x: usize, y: usize, dx: i32, dy: i32) -> usize {
+		let mut count = 0;
+		let (mut cx, mut cy) = (x as i32 + dx, y as i32 + dy);
+		while cx >= 0 && cy >= 0 && self.get(cx as usize, cy as usize) == Some(player) {
+			count += 1;
+			cx += dx;
+			cy += dy;
+		}
+		count
+	}
+
+	/// The cell just past the stones of `player` counted by `count_direction`, `None` when
+	/// the line runs into the edge of the board.
+	pub fn line_end(&self, player: i8, x: usize, y: usize, dx: i32, dy: i32) -> Option<i8> {
+		let step = self.count_direction(player, x, y, dx, dy) as i32 + 1;
+		let (ex, ey) = (x as i32 + step * dx, y as i32 + step * dy);
+		if ex < 0 || ey < 0 {
+			return None
+		}
+		self.get(ex as usize, ey as usize)
+	}
+
+	/// Length of the line through (x, y) along (dx, dy) once `player` has a stone on (x, y).
+	pub fn line_length(&self, player: i8, x: usize, y: usize, dx: i32, dy: i32) -> usize {
+		1 + self.count_direction(player, x, y, dx, dy) +
+			self.count_direction(player, x, y, -dx, -dy)
+	}
+}
@@ -0,0 +1,97 @@
+//! Gomoku without FRAME: the board, move validation, the rule sets and what a move does to
+//! the game. The pallet plays its games through this crate, and the node and off-chain
+//! clients can use it to check moves the same way.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use core::cmp;
+
+pub mod analysis;
+pub mod board;
+pub mod rules;
+
+#[cfg(test)]
+mod tests;
+
+pub use board::{Board, EMPTY, MAX_BOARD_SIZE};
+pub use rules::{Forbidden, RuleSet};
+
+/// Under the Pro opening, the least distance between the third stone and the center.
+pub const PRO_DISTANCE: usize = 3;
+
+/// Why a stone can not be placed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+	/// (x, y) is outside of the board.
+	OutOfBoard,
+	/// A stone is already on (x, y).
+	NotEmpty,
+	/// The stone is not a player index the board can hold.
+	InvalidStone,
+	/// The rule set does not allow the move.
+	Forbidden(Forbidden),
+}
+
+/// What a move did to the game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveOutcome {
+	/// The stone made a winning line.
+	Win,
+	/// The stone filled the last empty cell without winning.
+	Draw,
+	/// The game goes on.
+	Continue,
+}
+
+/// Whether (x, y) is an empty cell of the board.
+pub fn check_placement(board: &Board, x: usize, y: usize) -> Result<(), MoveError> {
+	if !board.contains(x, y) {
+		return Err(MoveError::OutOfBoard)
+	}
+	if !board.is_empty_at(x, y) {
+		return Err(MoveError::NotEmpty)
+	}
+	Ok(())
+}
+
+/// Place `stone` on (x, y) under `rule`, `black` being the seat that moved first. The board
+/// is left untouched when the move is not allowed.
+pub fn play(
+	board: &mut Board,
+	rule: RuleSet,
+	win_length: u8,
+	black: i8,
+	stone: i8,
+	x: usize,
+	y: usize,
+) -> Result<MoveOutcome, MoveError> {
+	check_placement(board, x, y)?;
+	if let Some(reason) = rule.forbidden(board, black, stone, x, y) {
+		return Err(MoveError::Forbidden(reason))
+	}
+	let win = rule.is_win(board, win_length, stone, x, y);
+	if !board.set(x, y, stone) {
+		return Err(MoveError::InvalidStone)
+	}
+	Ok(if win {
+		MoveOutcome::Win
+	} else if board.is_full() {
+		MoveOutcome::Draw
+	} else {
+		MoveOutcome::Continue
+	})
+}
+
+/// Under the Pro opening, the first stone must be in the center and the third one at least
+/// `PRO_DISTANCE` away from it. Other moves are not restricted.
+pub fn pro_opening_allows(board: &Board, move_index: usize, x: usize, y: usize) -> bool {
+	let (cx, cy) = (board.width as usize / 2, board.height as usize / 2);
+	let distance = cmp::max(cmp::max(x, cx) - cmp::min(x, cx), cmp::max(y, cy) - cmp::min(y, cy));
+	match move_index {
+		0 => distance == 0,
+		2 => distance >= PRO_DISTANCE,
+		_ => true,
+	}
+}
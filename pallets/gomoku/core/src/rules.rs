@@ -0,0 +1,116 @@
+//! Win conditions of the rule sets a game can be opened with.
+
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	analysis::Line,
+	board::{Board, DIRECTIONS, EMPTY},
+};
+
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Debug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum RuleSet {
+	/// A line of at least `win_length` stones wins, overlines included.
+	Freestyle,
+	/// Only a line of exactly `win_length` stones wins, overlines do not.
+	Standard,
+	/// Vietnamese Caro: a line of at least `win_length` stones wins unless opponent stones
+	/// block it at both ends. The edge of the board does not block.
+	Caro,
+	/// Renju: the first player (black) wins with exactly five and may not make a
+	/// double-three, a double-four or an overline. The second player (white) wins with any
+	/// five or more. Played with a win length of five.
+	Renju,
+}
+
+/// Why a black move is not allowed under [`RuleSet::Renju`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Forbidden {
+	DoubleThree,
+	DoubleFour,
+	Overline,
+}
+
+impl Default for RuleSet {
+	fn default() -> Self {
+		RuleSet::Freestyle
+	}
+}
+
+impl RuleSet {
+	/// Whether `player` placing a stone on (x, y) wins the game under this rule set.
+	pub fn is_win(&self, board: &Board, win_length: u8, player: i8, x: usize, y: usize) -> bool {
+		match self {
+			RuleSet::Freestyle => freestyle_win(board, win_length, player, x, y),
+			RuleSet::Standard => standard_win(board, win_length, player, x, y),
+			RuleSet::Caro => caro_win(board, win_length, player, x, y),
+			// black overlines are rejected as forbidden before the win is checked
+			RuleSet::Renju => freestyle_win(board, win_length, player, x, y),
+		}
+	}
+
+	/// Why `player` may not place a stone on (x, y), `None` when the move is allowed.
+	/// `black` is the index of the player who moved first.
+	pub fn forbidden(
+		&self,
+		board: &Board,
+		black: i8,
+		player: i8,
+		x: usize,
+		y: usize,
+	) -> Option<Forbidden> {
+		match self {
+			RuleSet::Renju if player == black => renju_forbidden(board, player, x, y),
+			_ => None,
+		}
+	}
+}
+
+fn freestyle_win(board: &Board, win_length: u8, player: i8, x: usize, y: usize) -> bool {
+	DIRECTIONS
+		.iter()
+		.any(|&(dx, dy)| board.line_length(player, x, y, dx, dy) >= win_length as usize)
+}
+
+fn standard_win(board: &Board, win_length: u8, player: i8, x: usize, y: usize) -> bool {
+	DIRECTIONS
+		.iter()
+		.any(|&(dx, dy)| board.line_length(player, x, y, dx, dy) == win_length as usize)
+}
+
+fn caro_win(board: &Board, win_length: u8, player: i8, x: usize, y: usize) -> bool {
+	let is_blocked =
+		|end: Option<i8>| matches!(end, Some(stone) if stone != EMPTY && stone != player);
+	DIRECTIONS.iter().any(|&(dx, dy)| {
+		board.line_length(player, x, y, dx, dy) >= win_length as usize &&
+			!(is_blocked(board.line_end(player, x, y, dx, dy)) &&
+				is_blocked(board.line_end(player, x, y, -dx, -dy)))
+	})
+}
+
+/// A five wins even if the same move would otherwise be forbidden.
+pub fn renju_forbidden(board: &Board, player: i8, x: usize, y: usize) -> Option<Forbidden> {
+	let lines: Vec<Line> = DIRECTIONS
+		.iter()
+		.map(|&(dx, dy)| Line::through(board, player, x, y, dx, dy))
+		.collect();
+
+	if lines.iter().any(Line::is_five) {
+		return None
+	}
+	if lines.iter().any(Line::is_overline) {
+		return Some(Forbidden::Overline)
+	}
+	if lines.iter().map(Line::fours).sum::<usize>() >= 2 {
+		return Some(Forbidden::DoubleFour)
+	}
+	if lines.iter().filter(|line| line.is_three()).count() >= 2 {
+		return Some(Forbidden::DoubleThree)
+	}
+	None
+}
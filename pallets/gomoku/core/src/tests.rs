@@ -0,0 +1,345 @@
+use crate::{
+	analysis::Line,
+	board::DIRECTIONS,
+	check_placement, play, pro_opening_allows,
+	rules::{renju_forbidden, Forbidden, RuleSet},
+	Board, MoveError, MoveOutcome, EMPTY, MAX_BOARD_SIZE,
+};
+use codec::{Decode, Encode};
+use proptest::prelude::*;
+
+const BLACK: i8 = 0;
+const WHITE: i8 = 1;
+
+fn board_with(stones: &[(usize, usize, i8)]) -> Board {
+	let mut board = Board::new(15, 15);
+	for &(x, y, stone) in stones {
+		assert!(board.set(x, y, stone));
+	}
+	board
+}
+
+/// `count` stones of `stone` in a row from (x, y) along (dx, dy).
+fn row(x: usize, y: usize, dx: i32, dy: i32, count: usize, stone: i8) -> Vec<(usize, usize, i8)> {
+	(0..count as i32)
+		.map(|i| ((x as i32 + i * dx) as usize, (y as i32 + i * dy) as usize, stone))
+		.collect()
+}
+
+#[test]
+fn new_board_should_be_empty() {
+	let board = Board::new(9, 13);
+	for x in 0..9 {
+		for y in 0..13 {
+			assert_eq!(board.get(x, y), Some(EMPTY));
+		}
+	}
+	assert_eq!(board.get(9, 0), None);
+	assert_eq!(board.get(0, 13), None);
+	assert!(!board.is_full());
+}
+
+#[test]
+fn board_should_keep_stones_of_every_seat() {
+	let mut board = Board::new(MAX_BOARD_SIZE, MAX_BOARD_SIZE);
+	for seat in 0..4 {
+		assert!(board.set(seat as usize, 0, seat));
+	}
+	for seat in 0..4 {
+		assert_eq!(board.get(seat as usize, 0), Some(seat));
+	}
+	assert!(!board.set(4, 0, 15));
+	assert!(!board.set(4, 0, -2));
+	assert_eq!(board.get(4, 0), Some(EMPTY));
+}
+
+#[test]
+fn board_should_encode_two_bits_per_cell() {
+	let board = board_with(&[(7, 7, BLACK), (7, 8, WHITE), (0, 0, 2)]);
+	// width, height, bits per cell, length prefix and 225 cells four to a byte
+	assert_eq!(board.encode().len(), 4 + 57);
+	assert_eq!(Board::decode(&mut &board.encode()[..]).ok(), Some(board));
+}
+
+#[test]
+fn board_should_serialize_unpacked_cells() {
+	let board = board_with(&[(0, 1, BLACK)]);
+	let json = serde_json::to_value(&board).unwrap();
+	assert_eq!(json["width"], 15);
+	assert_eq!(json["cells"][0], EMPTY);
+	assert_eq!(json["cells"][1], BLACK);
+	assert_eq!(json["cells"].as_array().map(Vec::len), Some(225));
+	assert_eq!(serde_json::from_value::<Board>(json).ok(), Some(board));
+}
+
+#[test]
+fn full_board_should_be_full() {
+	let mut board = Board::new(9, 9);
+	for x in 0..9 {
+		for y in 0..9 {
+			assert!(!board.is_full());
+			board.set(x, y, ((x + y) % 2) as i8);
+		}
+	}
+	assert!(board.is_full());
+}
+
+#[test]
+fn placement_should_need_an_empty_cell_on_the_board() {
+	let board = board_with(&[(7, 7, BLACK)]);
+	assert_eq!(check_placement(&board, 15, 0), Err(MoveError::OutOfBoard));
+	assert_eq!(check_placement(&board, 0, 15), Err(MoveError::OutOfBoard));
+	assert_eq!(check_placement(&board, 7, 7), Err(MoveError::NotEmpty));
+	assert_eq!(check_placement(&board, 7, 8), Ok(()));
+}
+
+#[test]
+fn five_should_win_in_every_direction() {
+	for &(dx, dy) in DIRECTIONS.iter() {
+		let (x, y) = (7 - 2 * dx, 7 - 2 * dy);
+		let mut board = board_with(&row(x as usize, y as usize, dx, dy, 4, BLACK));
+		let (lx, ly) = ((x + 4 * dx) as usize, (y + 4 * dy) as usize);
+		for rule in [RuleSet::Freestyle, RuleSet::Standard, RuleSet::Caro, RuleSet::Renju] {
+			assert!(rule.is_win(&board, 5, BLACK, lx, ly), "{:?} along {:?}", rule, (dx, dy));
+		}
+		assert_eq!(
+			play(&mut board, RuleSet::Freestyle, 5, BLACK, BLACK, lx, ly),
+			Ok(MoveOutcome::Win)
+		);
+	}
+}
+
+#[test]
+fn four_should_not_win() {
+	let board = board_with(&row(3, 7, 1, 0, 3, BLACK));
+	for rule in [RuleSet::Freestyle, RuleSet::Standard, RuleSet::Caro, RuleSet::Renju] {
+		assert!(!rule.is_win(&board, 5, BLACK, 6, 7));
+	}
+}
+
+#[test]
+fn overline_should_only_win_freestyle() {
+	// a gap at (7, 7) between three and two stones, filling it makes six
+	let mut stones = row(4, 7, 1, 0, 3, WHITE);
+	stones.extend(row(8, 7, 1, 0, 2, WHITE));
+	let board = board_with(&stones);
+	assert!(RuleSet::Freestyle.is_win(&board, 5, WHITE, 7, 7));
+	assert!(!RuleSet::Standard.is_win(&board, 5, WHITE, 7, 7));
+	assert!(RuleSet::Renju.is_win(&board, 5, WHITE, 7, 7), "white may overline");
+}
+
+#[test]
+fn caro_should_need_an_open_end() {
+	let mut stones = row(3, 7, 1, 0, 4, BLACK);
+	stones.push((2, 7, WHITE));
+	stones.push((8, 7, WHITE));
+	let board = board_with(&stones);
+	assert!(!RuleSet::Caro.is_win(&board, 5, BLACK, 7, 7), "blocked at both ends");
+	assert!(RuleSet::Freestyle.is_win(&board, 5, BLACK, 7, 7));
+
+	// the edge of the board does not block
+	let mut stones = row(1, 7, 1, 0, 4, BLACK);
+	stones.push((5, 7, WHITE));
+	let board = board_with(&stones);
+	assert!(RuleSet::Caro.is_win(&board, 5, BLACK, 0, 7));
+}
+
+#[test]
+fn win_length_should_be_respected() {
+	let board = board_with(&row(5, 5, 0, 1, 3, WHITE));
+	assert!(RuleSet::Freestyle.is_win(&board, 4, WHITE, 5, 8));
+	assert!(!RuleSet::Freestyle.is_win(&board, 5, WHITE, 5, 8));
+	assert!(RuleSet::Standard.is_win(&board, 4, WHITE, 5, 8));
+}
+
+#[test]
+fn renju_should_forbid_black_double_three() {
+	let board = board_with(&[(6, 7, BLACK), (8, 7, BLACK), (7, 6, BLACK), (7, 8, BLACK)]);
+	assert_eq!(renju_forbidden(&board, BLACK, 7, 7), Some(Forbidden::DoubleThree));
+	assert_eq!(RuleSet::Renju.forbidden(&board, BLACK, BLACK, 7, 7), Some(Forbidden::DoubleThree));
+	assert_eq!(RuleSet::Renju.forbidden(&board, BLACK, WHITE, 7, 7), None, "white is free");
+	assert_eq!(RuleSet::Freestyle.forbidden(&board, BLACK, BLACK, 7, 7), None);
+}
+
+#[test]
+fn renju_should_forbid_black_double_four() {
+	let mut stones = row(4, 7, 1, 0, 3, BLACK);
+	stones.extend(row(7, 4, 0, 1, 3, BLACK));
+	let board = board_with(&stones);
+	assert_eq!(renju_forbidden(&board, BLACK, 7, 7), Some(Forbidden::DoubleFour));
+}
+
+#[test]
+fn renju_should_forbid_black_overline() {
+	let mut stones = row(4, 7, 1, 0, 3, BLACK);
+	stones.extend(row(8, 7, 1, 0, 2, BLACK));
+	let mut board = board_with(&stones);
+	assert_eq!(renju_forbidden(&board, BLACK, 7, 7), Some(Forbidden::Overline));
+	assert_eq!(
+		play(&mut board, RuleSet::Renju, 5, BLACK, BLACK, 7, 7),
+		Err(MoveError::Forbidden(Forbidden::Overline))
+	);
+	assert_eq!(board.get(7, 7), Some(EMPTY), "a forbidden move is not placed");
+}
+
+#[test]
+fn renju_five_should_win_over_forbidden() {
+	// the five along the row wins even though the column makes a second four
+	let mut stones = row(3, 7, 1, 0, 4, BLACK);
+	stones.extend(row(7, 4, 0, 1, 3, BLACK));
+	let board = board_with(&stones);
+	assert_eq!(renju_forbidden(&board, BLACK, 7, 7), None);
+	assert!(RuleSet::Renju.is_win(&board, 5, BLACK, 7, 7));
+}
+
+#[test]
+fn line_should_tell_fours_and_threes() {
+	let board = board_with(&row(5, 7, 1, 0, 3, BLACK));
+	let straight = Line::through(&board, BLACK, 8, 7, 1, 0);
+	assert!(straight.is_straight_four());
+	assert_eq!(straight.fours(), 1);
+
+	let board = board_with(&row(6, 7, 1, 0, 2, BLACK));
+	let three = Line::through(&board, BLACK, 8, 7, 1, 0);
+	assert!(three.is_three());
+	assert_eq!(three.fours(), 0);
+
+	let mut stones = row(6, 7, 1, 0, 2, BLACK);
+	stones.push((5, 7, WHITE));
+	let board = board_with(&stones);
+	assert!(!Line::through(&board, BLACK, 8, 7, 1, 0).is_three(), "blocked three");
+}
+
+#[test]
+fn last_cell_should_draw() {
+	let mut board = Board::new(9, 9);
+	// columns of two alternating pairs never line up four of a kind
+	for x in 0..9 {
+		for y in 0..9 {
+			if (x, y) != (8, 8) {
+				board.set(x, y, (((y / 2) + x) % 2) as i8);
+			}
+		}
+	}
+	let stone = (((8 / 2) + 8) % 2) as i8;
+	assert_eq!(play(&mut board, RuleSet::Standard, 5, BLACK, stone, 8, 8), Ok(MoveOutcome::Draw));
+}
+
+#[test]
+fn invalid_stone_should_not_be_placed() {
+	let mut board = Board::new(15, 15);
+	assert_eq!(
+		play(&mut board, RuleSet::Freestyle, 5, BLACK, 16, 7, 7),
+		Err(MoveError::InvalidStone)
+	);
+	assert_eq!(board.get(7, 7), Some(EMPTY));
+}
+
+#[test]
+fn pro_opening_should_restrict_first_and_third_stone() {
+	let board = Board::new(15, 15);
+	assert!(pro_opening_allows(&board, 0, 7, 7));
+	assert!(!pro_opening_allows(&board, 0, 7, 8));
+	assert!(pro_opening_allows(&board, 1, 0, 0));
+	assert!(!pro_opening_allows(&board, 2, 9, 9));
+	assert!(pro_opening_allows(&board, 2, 10, 7));
+	assert!(pro_opening_allows(&board, 3, 7, 8));
+}
+
+fn any_board() -> impl Strategy<Value = (Board, Vec<(usize, usize, i8)>)> {
+	(9u8..=MAX_BOARD_SIZE, 9u8..=MAX_BOARD_SIZE).prop_flat_map(|(width, height)| {
+		let stone = (0..width as usize, 0..height as usize, -1i8..4);
+		prop::collection::vec(stone, 0..120).prop_map(move |stones| {
+			let mut board = Board::new(width, height);
+			for &(x, y, stone) in &stones {
+				assert!(board.set(x, y, stone));
+			}
+			(board, stones)
+		})
+	})
+}
+
+proptest! {
+	#[test]
+	fn board_should_read_back_the_last_stone_set((board, stones) in any_board()) {
+		let mut last = std::collections::BTreeMap::new();
+		for &(x, y, stone) in &stones {
+			last.insert((x, y), stone);
+		}
+		for x in 0..board.width as usize {
+			for y in 0..board.height as usize {
+				prop_assert_eq!(board.get(x, y), Some(*last.get(&(x, y)).unwrap_or(&EMPTY)));
+			}
+		}
+	}
+
+	#[test]
+	fn board_should_survive_encoding((board, _) in any_board()) {
+		prop_assert_eq!(Board::decode(&mut &board.encode()[..]).ok(), Some(board.clone()));
+		let json = serde_json::to_string(&board).unwrap();
+		prop_assert_eq!(serde_json::from_str::<Board>(&json).ok(), Some(board));
+	}
+
+	#[test]
+	fn line_length_should_not_depend_on_the_side_counted_from(
+		(board, _) in any_board(),
+		x in 0usize..9,
+		y in 0usize..9,
+		player in 0i8..4,
+	) {
+		for &(dx, dy) in DIRECTIONS.iter() {
+			prop_assert_eq!(
+				board.line_length(player, x, y, dx, dy),
+				board.line_length(player, x, y, -dx, -dy)
+			);
+		}
+	}
+
+	#[test]
+	fn wins_should_nest_between_rule_sets(
+		(board, _) in any_board(),
+		x in 0usize..9,
+		y in 0usize..9,
+		player in 0i8..4,
+		win_length in 4u8..=5,
+	) {
+		let freestyle = RuleSet::Freestyle.is_win(&board, win_length, player, x, y);
+		if RuleSet::Standard.is_win(&board, win_length, player, x, y) {
+			prop_assert!(freestyle);
+		}
+		if RuleSet::Caro.is_win(&board, win_length, player, x, y) {
+			prop_assert!(freestyle);
+		}
+		let longest = DIRECTIONS
+			.iter()
+			.map(|&(dx, dy)| board.line_length(player, x, y, dx, dy))
+			.max()
+			.unwrap_or_default();
+		prop_assert_eq!(freestyle, longest >= win_length as usize);
+	}
+
+	#[test]
+	fn play_should_place_only_allowed_moves(
+		(board, _) in any_board(),
+		x in 0usize..MAX_BOARD_SIZE as usize,
+		y in 0usize..MAX_BOARD_SIZE as usize,
+		stone in 0i8..2,
+	) {
+		let mut played = board.clone();
+		let placement = check_placement(&board, x, y);
+		let forbidden = RuleSet::Renju.forbidden(&board, BLACK, stone, x, y);
+		match play(&mut played, RuleSet::Renju, 5, BLACK, stone, x, y) {
+			Ok(outcome) => {
+				prop_assert!(placement.is_ok() && forbidden.is_none());
+				prop_assert_eq!(played.get(x, y), Some(stone));
+				let win = RuleSet::Renju.is_win(&board, 5, stone, x, y);
+				prop_assert_eq!(outcome == MoveOutcome::Win, win);
+				prop_assert_eq!(outcome == MoveOutcome::Draw, !win && played.is_full());
+			},
+			Err(error) => {
+				prop_assert!(placement.is_err() || forbidden.is_some(), "{:?}", error);
+				prop_assert_eq!(played, board);
+			},
+		}
+	}
+}
@@ -0,0 +1,30 @@
+//! Runtime API definition for querying gomoku games.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_gomoku::{board::Board, EndedGameInfo, GameInfo};
+
+sp_api::decl_runtime_apis! {
+	pub trait GomokuApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// A page of the games waiting for players.
+		fn open_games(page: u32) -> Vec<GameInfo<AccountId, Balance, BlockNumber>>;
+		/// A game in any status, ended ones until they are pruned. Ended games no longer list
+		/// their players, those are kept with the ended game.
+		fn game(game_id: [u8; 32]) -> Option<GameInfo<AccountId, Balance, BlockNumber>>;
+		/// The board of a running or ended game.
+		fn board(game_id: [u8; 32]) -> Option<Board>;
+		/// Player to move in a running game and the last block they can move in.
+		fn current_turn(game_id: [u8; 32]) -> Option<(AccountId, BlockNumber)>;
+		/// The game an account is seated at or hosting.
+		fn game_of(account: AccountId) -> Option<[u8; 32]>;
+		/// A page of ended games, most recent first.
+		fn ended_games(page: u32) -> Vec<EndedGameInfo<AccountId, Balance, BlockNumber>>;
+	}
+}
@@ -0,0 +1,178 @@
+//! RPC interface for querying gomoku games.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::{ApiError, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_gomoku_rpc_runtime_api::GomokuApi as GomokuRuntimeApi;
+use pallet_gomoku_rpc_runtime_api::{Board, EndedGameInfo, GameInfo};
+
+#[rpc]
+pub trait GomokuApi<BlockHash, AccountId, Balance, BlockNumber> {
+	/// A page of the games waiting for players.
+	#[rpc(name = "gomoku_openGames")]
+	fn open_games(
+		&self,
+		page: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<GameInfo<AccountId, Balance, BlockNumber>>>;
+
+	/// A game in any status, ended ones until they are pruned. Ended games no longer list
+	/// their players, those are kept with the ended game.
+	#[rpc(name = "gomoku_game")]
+	fn game(
+		&self,
+		game_id: H256,
+		at: Option<BlockHash>,
+	) -> Result<Option<GameInfo<AccountId, Balance, BlockNumber>>>;
+
+	/// The board of a running or ended game.
+	#[rpc(name = "gomoku_board")]
+	fn board(&self, game_id: H256, at: Option<BlockHash>) -> Result<Option<Board>>;
+
+	/// Player to move in a running game and the last block they can move in.
+	#[rpc(name = "gomoku_currentTurn")]
+	fn current_turn(
+		&self,
+		game_id: H256,
+		at: Option<BlockHash>,
+	) -> Result<Option<(AccountId, BlockNumber)>>;
+
+	/// The game an account is seated at or hosting.
+	#[rpc(name = "gomoku_gameOf")]
+	fn game_of(&self, account: AccountId, at: Option<BlockHash>) -> Result<Option<H256>>;
+
+	/// A page of ended games, most recent first.
+	#[rpc(name = "gomoku_endedGames")]
+	fn ended_games(
+		&self,
+		page: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<EndedGameInfo<AccountId, Balance, BlockNumber>>>;
+}
+
+/// Queries gomoku games through the runtime API of `client`.
+pub struct Gomoku<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Gomoku<C, Block> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> Gomoku<C, Block>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	/// The requested block, the best block by default.
+	fn block_id(&self, at: Option<Block::Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(e: ApiError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to query gomoku games.".into(),
+		data: Some(e.to_string().into()),
+	}
+}
+
+impl<C, Block, AccountId, Balance, BlockNumber>
+	GomokuApi<<Block as BlockT>::Hash, AccountId, Balance, BlockNumber> for Gomoku<C, Block>
+where
+	Block: BlockT,
+	C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
+	C::Api: GomokuRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	AccountId: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn open_games(
+		&self,
+		page: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<GameInfo<AccountId, Balance, BlockNumber>>> {
+		self.client
+			.runtime_api()
+			.open_games(&self.block_id(at), page)
+			.map_err(runtime_error)
+	}
+
+	fn game(
+		&self,
+		game_id: H256,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<GameInfo<AccountId, Balance, BlockNumber>>> {
+		self.client
+			.runtime_api()
+			.game(&self.block_id(at), game_id.to_fixed_bytes())
+			.map_err(runtime_error)
+	}
+
+	fn board(&self, game_id: H256, at: Option<<Block as BlockT>::Hash>) -> Result<Option<Board>> {
+		self.client
+			.runtime_api()
+			.board(&self.block_id(at), game_id.to_fixed_bytes())
+			.map_err(runtime_error)
+	}
+
+	fn current_turn(
+		&self,
+		game_id: H256,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<(AccountId, BlockNumber)>> {
+		self.client
+			.runtime_api()
+			.current_turn(&self.block_id(at), game_id.to_fixed_bytes())
+			.map_err(runtime_error)
+	}
+
+	fn game_of(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<H256>> {
+		let game_id = self
+			.client
+			.runtime_api()
+			.game_of(&self.block_id(at), account)
+			.map_err(runtime_error)?;
+		Ok(game_id.map(H256::from))
+	}
+
+	fn ended_games(
+		&self,
+		page: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<EndedGameInfo<AccountId, Balance, BlockNumber>>> {
+		self.client
+			.runtime_api()
+			.ended_games(&self.block_id(at), page)
+			.map_err(runtime_error)
+	}
+}
@@ -0,0 +1,291 @@
+//! Benchmarking setup for pallet-gomoku
+//!
+//! Every extrinsic is measured on its most expensive path: moves and draws that end the
+//! game pay the winner, take the rake and queue the ended game. `play` is measured twice, for
+//! a winning move and for the move filling the board, which pays every seat.
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Gomoku;
+use crate::{
+	board::{Board, MAX_BOARD_SIZE},
+	rules::RuleSet,
+};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::{
+	assert_ok,
+	sp_runtime::{traits::Bounded, Perbill},
+	sp_std::vec::Vec,
+	traits::{Currency, Get},
+};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+
+type ID = [u8; 32];
+
+fn ticket<T: Config>() -> BalanceOf<T> {
+	T::Currency::minimum_balance().saturating_mul(100u32.into())
+}
+
+fn funded<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+	let who = account(name, index, SEED);
+	T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 1_000u32.into());
+	who
+}
+
+fn secret(index: u32) -> [u8; 32] {
+	[index as u8; 32]
+}
+
+fn commitment<T: Config>(
+	settings: &GameSettings,
+	player: &T::AccountId,
+	index: u32,
+) -> Option<T::Hash> {
+	settings
+		.commit_reveal
+		.then(|| Gomoku::<T>::commitment_of(player, &secret(index)))
+}
+
+/// Open a game for `players` seats, only the host is seated.
+fn open_game<T: Config>(players: u32, settings: GameSettings) -> (ID, Vec<T::AccountId>) {
+	let settings = GameSettings { players: players as u8, ..settings };
+	// tickets and open fees land in accounts that must already exist
+	T::Currency::make_free_balance_be(&Gomoku::<T>::account_id(), T::Currency::minimum_balance());
+	T::Currency::make_free_balance_be(&Gomoku::<T>::fee_account(), T::Currency::minimum_balance());
+
+	let accounts: Vec<T::AccountId> = (0..players).map(|i| funded::<T>("player", i)).collect();
+	assert_ok!(Gomoku::<T>::open_and_join(
+		RawOrigin::Signed(accounts[0].clone()).into(),
+		ticket::<T>(),
+		settings,
+		commitment::<T>(&settings, &accounts[0], 0),
+	));
+	let game_id = Gomoku::<T>::game_hosting(&accounts[0]).unwrap();
+	(game_id, accounts)
+}
+
+/// Seat `accounts[1..seats]` at the game.
+fn join_game<T: Config>(game_id: ID, accounts: &[T::AccountId], seats: usize) {
+	let settings = Gomoku::<T>::games(game_id).unwrap().settings;
+	for (index, player) in accounts.iter().enumerate().take(seats).skip(1) {
+		assert_ok!(Gomoku::<T>::join(
+			RawOrigin::Signed(player.clone()).into(),
+			game_id,
+			commitment::<T>(&settings, player, index as u32),
+		));
+	}
+}
+
+/// Start a full game of `players`, the host moves first unless secrets are to be revealed.
+fn start_game<T: Config>(players: u32, settings: GameSettings) -> (ID, Vec<T::AccountId>) {
+	let (game_id, accounts) = open_game::<T>(players, settings);
+	join_game::<T>(game_id, &accounts, accounts.len());
+	assert_ok!(Gomoku::<T>::start(RawOrigin::Signed(accounts[0].clone()).into()));
+	(game_id, accounts)
+}
+
+fn play_at<T: Config>(player: &T::AccountId, x: u32, y: u32) {
+	assert_ok!(Gomoku::<T>::play(RawOrigin::Signed(player.clone()).into(), x, y));
+}
+
+/// Moves filling the largest board without a line of five, the players taking turns on the
+/// first cell that completes no line.
+fn drawn_moves(players: usize) -> Vec<(u32, u32)> {
+	let size = MAX_BOARD_SIZE as usize;
+	let mut board = Board::new(MAX_BOARD_SIZE, MAX_BOARD_SIZE);
+	(0..size * size)
+		.map(|index| {
+			let stone = (index % players) as i8;
+			let (x, y) = (0..size)
+				.flat_map(|x| (0..size).map(move |y| (x, y)))
+				.find(|&(x, y)| {
+					board.is_empty_at(x, y) && !RuleSet::Freestyle.is_win(&board, 5, stone, x, y)
+				})
+				.expect("every seat count up to 8 fills the board without a line");
+			board.set(x, y, stone);
+			(x as u32, y as u32)
+		})
+		.collect()
+}
+
+fn assert_ended<T: Config>(game_id: ID, outcome: GameOutcome<T::AccountId>) {
+	assert_eq!(Gomoku::<T>::ended_game(game_id).map(|ended| ended.outcome), Some(outcome));
+}
+
+benchmarks! {
+	open {
+		let caller = funded::<T>("caller", 0);
+		T::Currency::make_free_balance_be(&Gomoku::<T>::fee_account(), T::Currency::minimum_balance());
+	}: _(RawOrigin::Signed(caller.clone()), ticket::<T>(), GameSettings::default())
+	verify {
+		let game_id = Gomoku::<T>::game_hosting(&caller).unwrap();
+		assert!(Gomoku::<T>::is_listed(Lobby::Open, &game_id));
+	}
+
+	join {
+		let (game_id, accounts) = open_game::<T>(2, GameSettings::default());
+		let caller = accounts[1].clone();
+	}: _(RawOrigin::Signed(caller.clone()), game_id, None)
+	verify {
+		assert_eq!(Gomoku::<T>::game_playing(&caller), Some(game_id));
+	}
+
+	open_and_join {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 1_000u32.into());
+		T::Currency::make_free_balance_be(&Gomoku::<T>::account_id(), T::Currency::minimum_balance());
+		T::Currency::make_free_balance_be(&Gomoku::<T>::fee_account(), T::Currency::minimum_balance());
+	}: _(RawOrigin::Signed(caller.clone()), ticket::<T>(), GameSettings::default(), None)
+	verify {
+		assert_eq!(Gomoku::<T>::game_playing(&caller), Gomoku::<T>::game_hosting(&caller));
+	}
+
+	// the second player leaves and the host wins the pot
+	left {
+		let (game_id, accounts) = start_game::<T>(2, GameSettings::default());
+	}: _(RawOrigin::Signed(accounts[1].clone()))
+	verify {
+		assert_ended::<T>(game_id, GameOutcome::Forfeit(accounts[0].clone()));
+	}
+
+	// every seat but the last one taken, all of them refunded
+	cancel {
+		let players = T::MaxGomokuPlayer::get();
+		let (game_id, accounts) = open_game::<T>(players, GameSettings::default());
+		join_game::<T>(game_id, &accounts, accounts.len() - 1);
+	}: _(RawOrigin::Signed(accounts[0].clone()))
+	verify {
+		assert!(Gomoku::<T>::games(game_id).is_none());
+	}
+
+	start {
+		let players = T::MaxGomokuPlayer::get();
+		let (game_id, accounts) = open_game::<T>(players, GameSettings::default());
+		join_game::<T>(game_id, &accounts, accounts.len());
+	}: _(RawOrigin::Signed(accounts[0].clone()))
+	verify {
+		assert!(Gomoku::<T>::is_listed(Lobby::Started, &game_id));
+	}
+
+	// the winning move on the largest board
+	play {
+		let settings = GameSettings {
+			width: MAX_BOARD_SIZE,
+			height: MAX_BOARD_SIZE,
+			rule: RuleSet::Freestyle,
+			..Default::default()
+		};
+		let (game_id, accounts) = start_game::<T>(2, settings);
+		let edge = MAX_BOARD_SIZE as u32 - 1;
+		for x in 0..4 {
+			play_at::<T>(&accounts[0], x, 0);
+			play_at::<T>(&accounts[1], x, edge);
+		}
+	}: _(RawOrigin::Signed(accounts[0].clone()), 4, 0)
+	verify {
+		assert_ended::<T>(game_id, GameOutcome::Win(accounts[0].clone()));
+	}
+
+	// the last cell of the largest board ends a full table in a draw, every seat is paid
+	play_draw {
+		let players = T::MaxGomokuPlayer::get();
+		let settings = GameSettings {
+			width: MAX_BOARD_SIZE,
+			height: MAX_BOARD_SIZE,
+			win_length: 5,
+			rule: RuleSet::Freestyle,
+			..Default::default()
+		};
+		let (game_id, accounts) = start_game::<T>(players, settings);
+		let mut moves = drawn_moves(players as usize);
+		let (x, y) = moves.pop().unwrap();
+		let last = accounts[moves.len() % accounts.len()].clone();
+		for (index, (x, y)) in moves.into_iter().enumerate() {
+			play_at::<T>(&accounts[index % accounts.len()], x, y);
+		}
+	}: play(RawOrigin::Signed(last), x, y)
+	verify {
+		assert_ended::<T>(game_id, GameOutcome::Draw);
+	}
+
+	// the second player takes black after the three tentative stones of a Swap opening
+	choose_color {
+		let settings = GameSettings { opening: OpeningRule::Swap, ..Default::default() };
+		let (game_id, accounts) = start_game::<T>(2, settings);
+		for x in 0..3 {
+			play_at::<T>(&accounts[0], x, 0);
+		}
+	}: _(RawOrigin::Signed(accounts[1].clone()), Color::Black)
+	verify {
+		assert_eq!(Gomoku::<T>::opening(game_id), None);
+	}
+
+	// the last reveal of a full table picks the first player
+	reveal {
+		let players = T::MaxGomokuPlayer::get();
+		let settings = GameSettings { commit_reveal: true, ..Default::default() };
+		let (game_id, accounts) = start_game::<T>(players, settings);
+		let last = players - 1;
+		for (index, player) in accounts.iter().enumerate().take(last as usize) {
+			assert_ok!(Gomoku::<T>::reveal(
+				RawOrigin::Signed(player.clone()).into(),
+				secret(index as u32),
+			));
+		}
+	}: _(RawOrigin::Signed(accounts[last as usize].clone()), secret(last))
+	verify {
+		assert!(Gomoku::<T>::turn(game_id).is_some());
+	}
+
+	offer_draw {
+		let (game_id, accounts) = start_game::<T>(2, GameSettings::default());
+	}: _(RawOrigin::Signed(accounts[0].clone()))
+	verify {
+		assert_eq!(Gomoku::<T>::draw_offer(game_id), Some(accounts[0].clone()));
+	}
+
+	// both players are refunded their share of the pot
+	accept_draw {
+		let (game_id, accounts) = start_game::<T>(2, GameSettings::default());
+		assert_ok!(Gomoku::<T>::offer_draw(RawOrigin::Signed(accounts[0].clone()).into()));
+	}: _(RawOrigin::Signed(accounts[1].clone()))
+	verify {
+		assert_ended::<T>(game_id, GameOutcome::Draw);
+	}
+
+	// the host misses the first move and the other player wins
+	claim_timeout {
+		let (game_id, accounts) = start_game::<T>(2, GameSettings::default());
+		let deadline = Gomoku::<T>::turn(game_id).unwrap().deadline;
+		frame_system::Pallet::<T>::set_block_number(deadline + 1u32.into());
+	}: _(RawOrigin::Signed(accounts[1].clone()))
+	verify {
+		assert_ended::<T>(game_id, GameOutcome::Timeout(accounts[1].clone()));
+	}
+
+	set_house_rake {
+		let rake = Perbill::from_percent(5);
+	}: _(RawOrigin::Root, rake)
+	verify {
+		assert_eq!(Gomoku::<T>::house_rake(), rake);
+	}
+
+	set_fee_recipient {
+		let recipient: T::AccountId = account("recipient", 0, SEED);
+	}: _(RawOrigin::Root, Some(recipient.clone()))
+	verify {
+		assert_eq!(Gomoku::<T>::fee_recipient(), Some(recipient));
+	}
+
+	set_board_limits {
+		let limits = BoardLimits { min_size: 5, ..Default::default() };
+	}: _(RawOrigin::Root, limits)
+	verify {
+		assert_eq!(Gomoku::<T>::board_limits(), limits);
+	}
+
+	impl_benchmark_test_suite!(Gomoku, crate::mock::new_test_ext(), crate::mock::Test);
+}
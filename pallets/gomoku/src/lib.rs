@@ -2,30 +2,48 @@
 
 pub use pallet::*;
 
+pub use gomoku_core::{analysis, board, rules};
+
+pub mod migrations;
+pub mod weights;
+
 #[cfg(test)]
 mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
+		log,
 		pallet_prelude::*,
-		sp_runtime::{print, traits::Hash},
-		traits::{
-			tokens::{ExistenceRequirement, WithdrawReasons},
-			Currency, Randomness,
+		sp_runtime::{
+			print,
+			traits::{AccountIdConversion, CheckedDiv, Hash, One, Saturating, Zero},
+			PerThing, Perbill,
 		},
-		transactional,
+		sp_std::vec::Vec,
+		traits::{tokens::ExistenceRequirement, Currency, Randomness},
+		transactional, PalletId,
 	};
 	use frame_system::pallet_prelude::*;
 	use sp_io::hashing::blake2_256;
 
+	use crate::{
+		board::{Board, MAX_BOARD_SIZE},
+		rules::RuleSet,
+		weights::WeightInfo,
+	};
+	use gomoku_core::{MoveError, MoveOutcome};
+
 	#[cfg(feature = "std")]
 	use frame_support::serde::{Deserialize, Serialize};
 
-	type BalanceOf<T> =
+	pub type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 	type ID = [u8; 32];
 
@@ -36,6 +54,124 @@ pub mod pallet {
 		pub id: ID,
 		pub host: T::AccountId,
 		pub ticket: BalanceOf<T>,
+		/// The game is cancelled and every ticket refunded if it has not started by this block.
+		pub expire: T::BlockNumber,
+		pub status: GameStatus,
+		pub settings: GameSettings,
+	}
+
+	/// Board picked by the host when opening a game.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct GameSettings {
+		pub width: u8,
+		pub height: u8,
+		/// Stones in a row needed to win.
+		pub win_length: u8,
+		pub rule: RuleSet,
+		pub opening: OpeningRule,
+		/// Players commit to a secret on join and the secrets decide who moves first.
+		pub commit_reveal: bool,
+		/// Seats at the table, the game starts once all of them are taken.
+		pub players: u8,
+	}
+
+	impl Default for GameSettings {
+		fn default() -> Self {
+			Self {
+				width: 15,
+				height: 15,
+				win_length: 5,
+				rule: RuleSet::Freestyle,
+				opening: OpeningRule::Free,
+				commit_reveal: false,
+				players: 2,
+			}
+		}
+	}
+
+	/// How the first stones are placed and colors handed out.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum OpeningRule {
+		/// The player who starts the game moves first, then turns alternate.
+		Free,
+		/// The first stone goes in the center, black's second stone (the third stone) at
+		/// least `PRO_DISTANCE` away from it.
+		Pro,
+		/// The starting player places three stones (black, white, black), the other player
+		/// then picks a color.
+		Swap,
+		/// As `Swap`, but instead of picking a color the other player may place a white and
+		/// a black stone and leave the choice to the starting player.
+		Swap2,
+	}
+
+	/// Where a Swap or Swap2 game is in its opening, kept until colors are settled.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum OpeningPhase {
+		/// The starting player is placing the three tentative stones.
+		Tentative,
+		/// The other player picks a color, or under Swap2 places two more stones.
+		FirstChoice,
+		/// Swap2: the other player is placing the extra white and black stones.
+		ExtraStones,
+		/// Swap2: the starting player picks a color after the extra stones.
+		SecondChoice,
+	}
+
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum Color {
+		Black,
+		White,
+	}
+
+	pub use gomoku_core::PRO_DISTANCE;
+
+	/// Bounds on the settings a host can pick, set by governance.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct BoardLimits {
+		pub min_size: u8,
+		pub max_size: u8,
+		pub min_win_length: u8,
+		pub max_win_length: u8,
+	}
+
+	impl Default for BoardLimits {
+		fn default() -> Self {
+			Self { min_size: 9, max_size: MAX_BOARD_SIZE, min_win_length: 4, max_win_length: 5 }
+		}
+	}
+
+	impl BoardLimits {
+		pub fn is_valid(&self) -> bool {
+			self.min_size <= self.max_size &&
+				self.max_size <= MAX_BOARD_SIZE &&
+				self.min_win_length > 1 &&
+				self.min_win_length <= self.max_win_length &&
+				self.max_win_length <= self.min_size
+		}
+
+		pub fn allows(&self, settings: &GameSettings) -> bool {
+			let size = self.min_size..=self.max_size;
+			size.contains(&settings.width) &&
+				size.contains(&settings.height) &&
+				(self.min_win_length..=self.max_win_length).contains(&settings.win_length)
+		}
+
+		/// Cells of the largest board allowed, a game records at most one move per cell.
+		pub fn max_cells(&self) -> u32 {
+			self.max_size as u32 * self.max_size as u32
+		}
+	}
+
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct PlayerTurn<T: Config> {
+		pub player: T::AccountId,
+		/// Last block the player can move in, the opponent wins afterwards.
+		pub deadline: T::BlockNumber,
 	}
 
 	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
@@ -44,8 +180,51 @@ pub mod pallet {
 		pub id: ID,
 		pub host: T::AccountId,
 		pub ticket: BalanceOf<T>,
-		pub game_map: [[i8; 15]; 15],
-		pub winner: T::AccountId,
+		pub game_map: Board,
+		pub outcome: GameOutcome<T::AccountId>,
+		/// Players by seat, a move's player index points into it.
+		pub players: BoundedVec<T::AccountId, T::MaxGomokuPlayer>,
+		/// The game is pruned `EndedGameRetention` blocks after this one.
+		pub ended_at: T::BlockNumber,
+	}
+
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct Move<BlockNumber> {
+		pub player: u8,
+		pub x: u8,
+		pub y: u8,
+		pub block: BlockNumber,
+	}
+
+	/// Result of re-applying a move list on an empty board.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum ReplayOutcome {
+		/// The player index of the winning move.
+		Win(u8),
+		Draw,
+		Unfinished,
+	}
+
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum GameOutcome<AccountId> {
+		/// Five in a row.
+		Win(AccountId),
+		/// Full board or agreed draw, every ticket is returned.
+		Draw,
+		/// The opponent left the game.
+		Forfeit(AccountId),
+		/// The opponent missed the move deadline.
+		Timeout(AccountId),
+	}
+
+	impl<AccountId> GameOutcome<AccountId> {
+		pub fn winner(&self) -> Option<&AccountId> {
+			match self {
+				Self::Win(winner) | Self::Forfeit(winner) | Self::Timeout(winner) => Some(winner),
+				Self::Draw => None,
+			}
+		}
 	}
 
 	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
@@ -58,8 +237,81 @@ pub mod pallet {
 		Cancel,
 	}
 
+	impl GameStatus {
+		/// An open game either starts or gets cancelled, a started game can only end.
+		pub fn can_transition_to(&self, next: &GameStatus) -> bool {
+			matches!(
+				(self, next),
+				(GameStatus::Open, GameStatus::Start) |
+					(GameStatus::Open, GameStatus::Cancel) |
+					(GameStatus::Start, GameStatus::End)
+			)
+		}
+	}
+
+	/// Games returned per page of a lobby.
+	pub const LOBBY_PAGE_SIZE: u32 = 20;
+
+	/// The lists games are found by while they are open or being played.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum Lobby {
+		/// Games waiting for players.
+		Open,
+		/// Games being played.
+		Started,
+	}
+
+	/// Ended games returned per page by `ended_game_infos`.
+	pub const ENDED_GAMES_PAGE_SIZE: u64 = 20;
+
+	/// A game as served to clients through the `GomokuApi` runtime API.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct GameInfo<AccountId, Balance, BlockNumber> {
+		pub id: ID,
+		pub host: AccountId,
+		pub ticket: Balance,
+		pub expire: BlockNumber,
+		pub status: GameStatus,
+		pub settings: GameSettings,
+		/// Players by seat.
+		pub players: Vec<AccountId>,
+		/// Players out of the game, they keep their seat.
+		pub eliminated: Vec<AccountId>,
+	}
+
+	/// An ended game as served to clients through the `GomokuApi` runtime API.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct EndedGameInfo<AccountId, Balance, BlockNumber> {
+		pub id: ID,
+		pub host: AccountId,
+		pub ticket: Balance,
+		pub board: Board,
+		pub outcome: GameOutcome<AccountId>,
+		/// Players by seat.
+		pub players: Vec<AccountId>,
+		/// The game is pruned `EndedGameRetention` blocks after this one.
+		pub ended_at: BlockNumber,
+	}
+
+	pub type GameInfoOf<T> = GameInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+	pub type EndedGameInfoOf<T> = EndedGameInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Configure the pallet by specifying the parameters and types it depends on.
@@ -70,6 +322,10 @@ pub mod pallet {
 
 		type Currency: Currency<Self::AccountId>;
 
+		/// The escrow account holding game tickets and open fees is derived from this id.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
 		type GameRandomness: Randomness<Self::Hash, Self::BlockNumber>;
 
 		#[pallet::constant]
@@ -79,29 +335,42 @@ pub mod pallet {
 		type MaxGame: Get<u32>;
 
 		#[pallet::constant]
-		type MaxOpenGame: Get<u32>;
+		type OpenGameFee: Get<u32>;
 
+		/// Number of blocks an ended game is kept before `on_idle` prunes it.
 		#[pallet::constant]
-		type MaxStartGame: Get<u32>;
+		type EndedGameRetention: Get<Self::BlockNumber>;
 
+		/// Moves kept per game, at least the number of cells on the largest board.
 		#[pallet::constant]
-		type OpenGameFee: Get<u32>;
+		type MaxMoves: Get<u32>;
+
+		/// Number of blocks a player has to make a move.
+		#[pallet::constant]
+		type MoveTimeout: Get<Self::BlockNumber>;
 
+		/// Number of blocks an open game waits for players before it expires.
 		#[pallet::constant]
-		type MaxEndedGame: Get<u32>;
+		type OpenGameTimeout: Get<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	// Errors.
+	#[derive(PartialEq)]
 	#[pallet::error]
 	pub enum Error<T> {
 		GameNotExist,
 		GameNotOpen,
+		GameNotStarted,
+		InvalidStatusTransition,
+		NotGameHost,
 		GameIdUsed,
 		GameOverflow,
 		GameOpenNotFound,
 		GameStartNotFound,
-		GameEndedNotFound,
-		ExceedGameOpen,
+		TicketTooLow,
 		PlayerExceed,
 		PlayersOverflow,
 		PlayersNotFound,
@@ -115,6 +384,51 @@ pub mod pallet {
 		GameMapNotFound,
 		PlaceNotEmpty,
 		PlaceNotCorrect,
+		MovesOverflow,
+		InvalidPlayerIndex,
+		GameAlreadyEnded,
+		TurnExpired,
+		TurnNotExpired,
+		CanNotClaimOwnTurn,
+		DrawNotOffered,
+		CanNotAcceptOwnDraw,
+		BoardSizeNotAllowed,
+		WinLengthNotAllowed,
+		InvalidBoardLimits,
+		/// The move is a double-three, double-four or overline for black under Renju.
+		ForbiddenMove,
+		/// The move breaks the placement rules of the game's opening.
+		OpeningMoveNotAllowed,
+		/// A color has to be picked with `choose_color` before the next stone.
+		ColorNotChosen,
+		NotChoosingColor,
+		/// The game picks the first player by commit-reveal and needs a commitment on join.
+		CommitmentMissing,
+		CommitmentNotExpected,
+		NotRevealing,
+		RevealExpired,
+		RevealNotExpired,
+		/// The secret does not match the commitment made on join.
+		InvalidReveal,
+		AlreadyRevealed,
+		/// Every player has to reveal before the first move.
+		RevealPending,
+		/// Renju and the Pro, Swap and Swap2 openings are played by two players.
+		RuleNeedsTwoPlayers,
+		/// A game seats from two to `MaxGomokuPlayer` players.
+		PlayersNotAllowed,
+		DrawNeedsTwoPlayers,
+	}
+
+	impl<T: Config> From<MoveError> for Error<T> {
+		fn from(error: MoveError) -> Self {
+			match error {
+				MoveError::OutOfBoard => <Error<T>>::PlaceNotCorrect,
+				MoveError::NotEmpty => <Error<T>>::PlaceNotEmpty,
+				MoveError::InvalidStone => <Error<T>>::InvalidPlayerIndex,
+				MoveError::Forbidden(_) => <Error<T>>::ForbiddenMove,
+			}
+		}
 	}
 
 	// Events.
@@ -123,6 +437,31 @@ pub mod pallet {
 	pub enum Event<T: Config> {
 		NewGameOpen(ID, T::AccountId, BalanceOf<T>),
 		PlayerJoinGame(T::AccountId, ID),
+		/// A game started, with its players in turn order.
+		GameStarted(ID, BoundedVec<T::AccountId, T::MaxGomokuPlayer>),
+		/// A stone was placed on (x, y).
+		MovePlayed(ID, T::AccountId, u8, u8),
+		/// A game ended with its winner, the total paid out to players, the rake collected and
+		/// the first block it may be pruned in.
+		GameEnded(
+			ID,
+			GameOutcome<T::AccountId>,
+			Option<T::AccountId>,
+			BalanceOf<T>,
+			BalanceOf<T>,
+			T::BlockNumber,
+		),
+		GameCancelled(ID),
+		PlayerLeft(ID, T::AccountId),
+		/// A player missed a deadline and is out of the game.
+		PlayerTimedOut(ID, T::AccountId),
+		/// A player picked a color in a Swap or Swap2 opening.
+		ColorChosen(ID, T::AccountId, Color),
+		/// A ticket went back to a player.
+		Refunded(ID, T::AccountId, BalanceOf<T>),
+		/// An ended game past its retention was removed, it can still be read at the parent
+		/// block.
+		EndedGamePruned(ID),
 	}
 
 	#[pallet::storage]
@@ -133,19 +472,39 @@ pub mod pallet {
 	#[pallet::getter(fn open_game_fee)]
 	pub(super) type OpenGameFee<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+	/// Share of every pot taken by the house.
+	#[pallet::storage]
+	#[pallet::getter(fn house_rake)]
+	pub(super) type HouseRake<T: Config> = StorageValue<_, Perbill, ValueQuery>;
+
+	/// Account receiving the rake and open fees, the escrow account keeps them when unset.
 	#[pallet::storage]
-	#[pallet::getter(fn max_gomoku_player)]
-	pub(super) type MaxGomoku<T: Config> = StorageValue<_, u8, ValueQuery>;
+	#[pallet::getter(fn fee_recipient)]
+	pub(super) type FeeRecipient<T: Config> = StorageValue<_, T::AccountId>;
 
 	#[pallet::storage]
-	#[pallet::getter(fn game_open)]
-	pub(super) type GameOpen<T: Config> =
-		StorageValue<_, BoundedVec<ID, T::MaxOpenGame>, ValueQuery>;
+	#[pallet::getter(fn board_limits)]
+	pub(super) type BoardLimitsOf<T: Config> = StorageValue<_, BoardLimits, ValueQuery>;
 
+	/// Games of each lobby by slot, slots below the lobby size are taken.
 	#[pallet::storage]
-	#[pallet::getter(fn game_start)]
-	pub(super) type GameStart<T: Config> =
-		StorageValue<_, BoundedVec<ID, T::MaxStartGame>, ValueQuery>;
+	#[pallet::getter(fn lobby_game)]
+	pub(super) type LobbyGames<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, Lobby, Twox64Concat, u32, ID>;
+
+	/// Lobby and slot of every listed game.
+	#[pallet::storage]
+	#[pallet::getter(fn lobby_slot)]
+	pub(super) type LobbySlots<T: Config> = StorageMap<_, Twox64Concat, ID, (Lobby, u32)>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn lobby_size)]
+	pub(super) type LobbySize<T: Config> = StorageMap<_, Twox64Concat, Lobby, u32, ValueQuery>;
+
+	/// Slot the expiry sweep in `on_idle` goes on from in the next block.
+	#[pallet::storage]
+	#[pallet::getter(fn lobby_cursor)]
+	pub(super) type LobbyCursor<T: Config> = StorageMap<_, Twox64Concat, Lobby, u32, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn games)]
@@ -167,31 +526,87 @@ pub mod pallet {
 	#[pallet::getter(fn ended_game)]
 	pub(super) type EndedGames<T: Config> = StorageMap<_, Twox64Concat, ID, EndedGame<T>>;
 
+	/// Ended games in the order they ended, by sequence number.
+	#[pallet::storage]
+	#[pallet::getter(fn ended_game_at)]
+	pub(super) type EndedGameQueue<T: Config> = StorageMap<_, Twox64Concat, u64, ID>;
+
+	/// Sequence number of the oldest ended game kept.
+	#[pallet::storage]
+	#[pallet::getter(fn ended_head)]
+	pub(super) type EndedHead<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Sequence number the next ended game gets.
 	#[pallet::storage]
-	#[pallet::getter(fn get_ended_games)]
-	pub(super) type GetEndedGames<T: Config> =
-		StorageValue<_, BoundedVec<ID, T::MaxEndedGame>, ValueQuery>;
+	#[pallet::getter(fn ended_tail)]
+	pub(super) type EndedTail<T: Config> = StorageValue<_, u64, ValueQuery>;
 
 	// GAME LOGIC STORAGE
 	#[pallet::storage]
 	#[pallet::getter(fn gomoku_game)]
-	pub(super) type GomokuGame<T: Config> = StorageMap<_, Twox64Concat, ID, [[i8; 15]; 15]>;
+	pub(super) type GomokuGame<T: Config> = StorageMap<_, Twox64Concat, ID, Board>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn turn)]
-	pub(super) type Turn<T: Config> = StorageMap<_, Twox64Concat, ID, T::AccountId>;
+	pub(super) type Turn<T: Config> = StorageMap<_, Twox64Concat, ID, PlayerTurn<T>>;
+
+	/// Phase of a Swap or Swap2 opening: who places the next stones and whether a color
+	/// has to be chosen first. Removed once colors are settled.
+	#[pallet::storage]
+	#[pallet::getter(fn opening)]
+	pub(super) type Opening<T: Config> = StorageMap<_, Twox64Concat, ID, OpeningPhase>;
+
+	/// Hash of (account, secret) each player committed to on join.
+	#[pallet::storage]
+	#[pallet::getter(fn commitment)]
+	pub(super) type Commitments<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, ID, Blake2_128Concat, T::AccountId, T::Hash>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn revealed)]
+	pub(super) type Reveals<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, ID, Blake2_128Concat, T::AccountId, [u8; 32]>;
+
+	/// Last block to reveal in, set while a started game waits for its reveals.
+	#[pallet::storage]
+	#[pallet::getter(fn reveal_deadline)]
+	pub(super) type RevealDeadline<T: Config> = StorageMap<_, Twox64Concat, ID, T::BlockNumber>;
+
+	/// Players out of a started game, they keep their seat and stones but take no turns.
+	#[pallet::storage]
+	#[pallet::getter(fn eliminated)]
+	pub(super) type Eliminated<T: Config> =
+		StorageMap<_, Twox64Concat, ID, BoundedVec<T::AccountId, T::MaxGomokuPlayer>, ValueQuery>;
+
+	/// Every move of a game in play order, kept until the ended game is pruned.
+	#[pallet::storage]
+	#[pallet::getter(fn game_moves)]
+	pub(super) type GameMoves<T: Config> =
+		StorageMap<_, Twox64Concat, ID, BoundedVec<Move<T::BlockNumber>, T::MaxMoves>, ValueQuery>;
+
+	/// Pending draw offer of a game, cleared by the next move.
+	#[pallet::storage]
+	#[pallet::getter(fn draw_offer)]
+	pub(super) type DrawOffer<T: Config> = StorageMap<_, Twox64Concat, ID, T::AccountId>;
 
 	//** Genesis Conguration **//
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub open_fee: BalanceOf<T>,
-		pub max_gomoku_player: u8,
+		pub house_rake: Perbill,
+		pub fee_recipient: Option<T::AccountId>,
+		pub board_limits: BoardLimits,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> Self {
-			Self { open_fee: Default::default(), max_gomoku_player: 2u8 }
+			Self {
+				open_fee: Default::default(),
+				house_rake: Perbill::from_percent(1),
+				fee_recipient: None,
+				board_limits: Default::default(),
+			}
 		}
 	}
 
@@ -199,77 +614,217 @@ pub mod pallet {
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self) {
 			<OpenGameFee<T>>::put(self.open_fee);
-			<MaxGomoku<T>>::put(self.max_gomoku_player);
+			<HouseRake<T>>::put(self.house_rake);
+			if let Some(ref fee_recipient) = self.fee_recipient {
+				<FeeRecipient<T>>::put(fee_recipient);
+			}
+			assert!(self.board_limits.is_valid(), "board limits are not valid");
+			assert!(
+				self.board_limits.max_cells() <= T::MaxMoves::get(),
+				"board limits allow more moves than MaxMoves"
+			);
+			<BoardLimitsOf<T>>::put(self.board_limits);
+
+			// keep the escrow account alive so payouts never reap it
+			let account_id = <Pallet<T>>::account_id();
+			let min = T::Currency::minimum_balance();
+			if T::Currency::free_balance(&account_id) < min {
+				let _ = T::Currency::make_free_balance_be(&account_id, min);
+			}
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(block_number: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			let used_weight = Self::settle_expired_games(block_number, remaining_weight);
+			used_weight.saturating_add(Self::prune_ended_games(
+				block_number,
+				remaining_weight.saturating_sub(used_weight),
+			))
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::v1::migrate::<T>()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
 		}
 	}
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::weight(100)]
-		pub fn open(origin: OriginFor<T>, ticket: BalanceOf<T>) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::open())]
+		pub fn open(
+			origin: OriginFor<T>,
+			ticket: BalanceOf<T>,
+			settings: GameSettings,
+		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			let id = Self::open_game(sender.clone(), ticket)?;
+			let id = Self::open_game(sender.clone(), ticket, settings)?;
 			Self::charge_fee_open_game(&sender)?;
 			Self::deposit_event(Event::NewGameOpen(id, sender, ticket));
 			Ok(())
 		}
 
-		#[pallet::weight(100)]
-		pub fn join(origin: OriginFor<T>, game_id: ID) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::join())]
+		pub fn join(
+			origin: OriginFor<T>,
+			game_id: ID,
+			commitment: Option<T::Hash>,
+		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			let game = Self::join_game(sender.clone(), &game_id)?;
+			let game = Self::join_game(sender.clone(), &game_id, commitment)?;
 			Self::charge_join_game(&sender, game.ticket)?;
 			Self::deposit_event(Event::PlayerJoinGame(sender, game_id));
 			Ok(())
 		}
 
-		#[pallet::weight(100)]
-		pub fn open_and_join(origin: OriginFor<T>, ticket: BalanceOf<T>) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::open_and_join())]
+		pub fn open_and_join(
+			origin: OriginFor<T>,
+			ticket: BalanceOf<T>,
+			settings: GameSettings,
+			commitment: Option<T::Hash>,
+		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			let id = Self::open_game(sender.clone(), ticket)?;
+			let id = Self::open_game(sender.clone(), ticket, settings)?;
 			Self::charge_fee_open_game(&sender)?;
 			Self::deposit_event(Event::NewGameOpen(id, sender.clone(), ticket));
 
-			let game = Self::join_game(sender.clone(), &id)?;
+			let game = Self::join_game(sender.clone(), &id, commitment)?;
 			Self::charge_join_game(&sender, game.ticket)?;
 			Self::deposit_event(Event::PlayerJoinGame(sender, id));
 			Ok(())
 		}
 
-		#[pallet::weight(100)]
+		#[pallet::weight(T::WeightInfo::left())]
 		pub fn left(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::left_game(sender)?;
 			Ok(())
 		}
 
-		#[pallet::weight(100)]
+		#[pallet::weight(T::WeightInfo::cancel())]
+		pub fn cancel(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::cancel_game(&sender)?;
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::start())]
 		pub fn start(origin: OriginFor<T>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			Self::start_game(&sender)?;
 			Ok(())
 		}
 
-		#[pallet::weight(1)]
+		/// Charged for the heavier of a winning move and the move filling the board.
+		#[pallet::weight(T::WeightInfo::play().max(T::WeightInfo::play_draw()))]
 		pub fn play(origin: OriginFor<T>, x: u32, y: u32) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			Self::play_game(&sender, x as usize, y as usize)?;
 			Ok(())
 		}
+
+		/// Pick a color during a Swap or Swap2 opening. The player taking black owns the
+		/// black stones already on the board.
+		#[pallet::weight(T::WeightInfo::choose_color())]
+		pub fn choose_color(origin: OriginFor<T>, color: Color) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::choose_color_game(&sender, color)?;
+			Ok(())
+		}
+
+		/// Reveal the secret committed to on join. Once every player has revealed, the
+		/// XOR of the secrets picks the first player.
+		#[pallet::weight(T::WeightInfo::reveal())]
+		pub fn reveal(origin: OriginFor<T>, secret: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::reveal_secret(&sender, secret)?;
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::offer_draw())]
+		pub fn offer_draw(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::offer_draw_game(sender)?;
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::accept_draw())]
+		pub fn accept_draw(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::accept_draw_game(&sender)?;
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::claim_timeout())]
+		pub fn claim_timeout(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::claim_timeout_win(&sender)?;
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::set_house_rake())]
+		pub fn set_house_rake(origin: OriginFor<T>, rake: Perbill) -> DispatchResult {
+			ensure_root(origin)?;
+			<HouseRake<T>>::put(rake);
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::set_fee_recipient())]
+		pub fn set_fee_recipient(
+			origin: OriginFor<T>,
+			fee_recipient: Option<T::AccountId>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			match fee_recipient {
+				Some(account) => <FeeRecipient<T>>::put(account),
+				None => <FeeRecipient<T>>::kill(),
+			}
+			Ok(())
+		}
+
+		#[pallet::weight(T::WeightInfo::set_board_limits())]
+		pub fn set_board_limits(origin: OriginFor<T>, limits: BoardLimits) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(limits.is_valid(), <Error<T>>::InvalidBoardLimits);
+			ensure!(limits.max_cells() <= T::MaxMoves::get(), <Error<T>>::InvalidBoardLimits);
+			<BoardLimitsOf<T>>::put(limits);
+			Ok(())
+		}
 	}
 
 	//** Our helper functions.**//
 
 	impl<T: Config> Pallet<T> {
 		#[transactional]
-		pub fn open_game(sender: T::AccountId, ticket: BalanceOf<T>) -> Result<ID, Error<T>> {
+		pub fn open_game(
+			sender: T::AccountId,
+			ticket: BalanceOf<T>,
+			settings: GameSettings,
+		) -> Result<ID, Error<T>> {
 			Self::is_host_available(&sender)?;
+			ensure!(ticket >= T::Currency::minimum_balance(), <Error<T>>::TicketTooLow);
+			Self::check_settings(&settings)?;
 			let new_game_cnt = Self::game_cnt().checked_add(1).ok_or(<Error<T>>::GameOverflow)?;
 			<GameCnt<T>>::put(new_game_cnt);
 			let id = Self::gen_id()?;
 			Self::is_id_available(id)?;
-			let game = Game::<T> { id, host: sender.clone(), ticket };
+			let expire = Self::block_number().saturating_add(T::OpenGameTimeout::get());
+			let game = Game::<T> {
+				id,
+				host: sender.clone(),
+				ticket,
+				expire,
+				status: GameStatus::Open,
+				settings,
+			};
 
-			<GameOpen<T>>::try_mutate(|game_open| game_open.try_push(id))
-				.map_err(|_| <Error<T>>::ExceedGameOpen)?;
+			Self::list_game(Lobby::Open, id)?;
 			<Games<T>>::insert(id, game);
 			<GameHosting<T>>::insert(sender, id);
 			Ok(id)
@@ -279,54 +834,369 @@ pub mod pallet {
 		pub fn start_game(sender: &T::AccountId) -> Result<(), Error<T>> {
 			let id_game_playing = Self::get_game_playing(sender)?;
 			let players = Self::players(id_game_playing);
+			let seats = Self::get_game(&id_game_playing)?.settings.players;
 
-			ensure!(players.len() as u8 == Self::max_gomoku_player(), <Error<T>>::NotEnoughPlayer);
+			ensure!(players.len() == seats as usize, <Error<T>>::NotEnoughPlayer);
+			let game = Self::set_game_status(&id_game_playing, GameStatus::Start)?;
 
-			<GomokuGame<T>>::insert(id_game_playing, [[-1i8; 15]; 15]);
-
-			<GameOpen<T>>::try_mutate(|id_vec| {
-				if let Some(ind) = id_vec.iter().position(|&id| id == id_game_playing) {
-					id_vec.swap_remove(ind);
-					return Ok(())
-				}
-				Err(())
-			})
-			.map_err(|_| <Error<T>>::GameOpenNotFound)?;
+			let board = Board::new(game.settings.width, game.settings.height);
+			<GomokuGame<T>>::insert(id_game_playing, board);
+			if let OpeningRule::Swap | OpeningRule::Swap2 = game.settings.opening {
+				<Opening<T>>::insert(id_game_playing, OpeningPhase::Tentative);
+			}
 
-			<GameStart<T>>::try_mutate(|game_start| game_start.try_push(id_game_playing))
-				.map_err(|_| <Error<T>>::GameNotExist)?;
+			Self::unlist_game(Lobby::Open, &id_game_playing)?;
+			Self::list_game(Lobby::Started, id_game_playing)?;
 
-			<Turn<T>>::insert(id_game_playing, sender);
+			if game.settings.commit_reveal {
+				let deadline = Self::block_number().saturating_add(T::MoveTimeout::get());
+				<RevealDeadline<T>>::insert(id_game_playing, deadline);
+			} else {
+				Self::set_turn(&id_game_playing, sender.clone());
+				Self::deposit_event(Event::GameStarted(
+					id_game_playing,
+					Self::turn_order(&id_game_playing, sender)?,
+				));
+			}
 			Ok(())
 		}
 
 		#[transactional]
-		pub fn play_game(sender: &T::AccountId, x: usize, y: usize) -> Result<(), Error<T>> {
+		pub fn play_game(sender: &T::AccountId, x: usize, y: usize) -> DispatchResult {
 			let game_playing_id = Self::get_game_playing(sender)?;
+			ensure!(!<RevealDeadline<T>>::contains_key(game_playing_id), <Error<T>>::RevealPending);
 			ensure!(Self::get_turn(sender, game_playing_id)?, <Error<T>>::NotYourTurn);
+			ensure!(!Self::is_turn_expired(&game_playing_id)?, <Error<T>>::TurnExpired);
 			let player_index = Self::get_player_index(&game_playing_id, &sender)?;
+			let game = Self::get_game(&game_playing_id)?;
+			let mut gomoku_game =
+				Self::gomoku_game(game_playing_id).ok_or(<Error<T>>::GameMapNotFound)?;
+			gomoku_core::check_placement(&gomoku_game, x, y).map_err(<Error<T>>::from)?;
+
+			let moves = Self::game_moves(game_playing_id);
+			let black = moves.first().map_or(player_index, |first| first.player as i8);
+			let stone = match Self::opening(game_playing_id) {
+				Some(OpeningPhase::SecondChoice) => return Err(<Error<T>>::ColorNotChosen.into()),
+				Some(OpeningPhase::FirstChoice) if game.settings.opening != OpeningRule::Swap2 =>
+					return Err(<Error<T>>::ColorNotChosen.into()),
+				// opening stones alternate colors whoever places them
+				Some(_) => Self::stone_of_move(black, moves.len()),
+				None => player_index,
+			};
+			Self::check_opening_move(&game.settings, &gomoku_game, moves.len(), x, y)?;
+			let outcome = gomoku_core::play(
+				&mut gomoku_game,
+				game.settings.rule,
+				game.settings.win_length,
+				black,
+				stone,
+				x,
+				y,
+			)
+			.map_err(<Error<T>>::from)?;
+			Self::record_move(&game_playing_id, stone, x, y)?;
+			Self::deposit_event(Event::MovePlayed(
+				game_playing_id,
+				sender.clone(),
+				x as u8,
+				y as u8,
+			));
+
+			match outcome {
+				MoveOutcome::Win => {
+					let outcome = GameOutcome::Win(sender.clone());
+					Self::finish_game(outcome, game_playing_id, gomoku_game)?;
+				},
+				MoveOutcome::Draw =>
+					Self::finish_game(GameOutcome::Draw, game_playing_id, gomoku_game)?,
+				MoveOutcome::Continue =>
+					Self::continue_game(sender, &game_playing_id, gomoku_game)?,
+			}
+			Ok(())
+		}
+
+		/// Under Pro, the first stone must be in the center and the third one at least
+		/// `PRO_DISTANCE` away from it.
+		pub fn check_opening_move(
+			settings: &GameSettings,
+			game_map: &Board,
+			move_index: usize,
+			x: usize,
+			y: usize,
+		) -> Result<(), Error<T>> {
+			if settings.opening != OpeningRule::Pro {
+				return Ok(())
+			}
 			ensure!(
-				(Self::gomoku_game(game_playing_id).unwrap())[x][y] == -1i8,
-				<Error<T>>::PlaceNotEmpty
+				gomoku_core::pro_opening_allows(game_map, move_index, x, y),
+				<Error<T>>::OpeningMoveNotAllowed
 			);
+			Ok(())
+		}
 
-			// check winner
-			let gomoku_game = Self::gomoku_game(game_playing_id).unwrap();
+		/// The seat owning the stone of the `move_index`th move, counted from black's first.
+		pub fn stone_of_move(black: i8, move_index: usize) -> i8 {
+			if move_index % 2 == 0 {
+				black
+			} else {
+				1 - black
+			}
+		}
 
-			let game_result = Self::check_winner(gomoku_game, player_index, x, y)?;
+		/// Move a Swap or Swap2 opening on after a stone, returning the phase it is now in.
+		pub fn advance_opening(game_id: &ID) -> Option<OpeningPhase> {
+			let phase = Self::opening(game_id)?;
+			let placed = Self::game_moves(game_id).len();
+			let next = match phase {
+				OpeningPhase::Tentative if placed >= 3 => OpeningPhase::FirstChoice,
+				OpeningPhase::FirstChoice => OpeningPhase::ExtraStones,
+				OpeningPhase::ExtraStones if placed >= 5 => OpeningPhase::SecondChoice,
+				phase => phase,
+			};
+			<Opening<T>>::insert(game_id, next);
+			Some(next)
+		}
 
-			if game_result == false {
-				Self::continue_game(sender, &game_playing_id, x, y, player_index)?;
-			} else {
-				Self::finish_game(sender.clone(), game_playing_id, gomoku_game)?;
+		/// Settle colors by seat: taking black means taking the seat that owns the first
+		/// stone. Then white or black moves next depending on how many stones are down.
+		#[transactional]
+		pub fn choose_color_game(sender: &T::AccountId, color: Color) -> Result<(), Error<T>> {
+			let game_id = Self::get_game_playing(sender)?;
+			Self::is_game_started(&game_id)?;
+			match Self::opening(game_id) {
+				Some(OpeningPhase::FirstChoice) | Some(OpeningPhase::SecondChoice) => (),
+				_ => return Err(<Error<T>>::NotChoosingColor),
 			}
+			ensure!(Self::get_turn(sender, game_id)?, <Error<T>>::NotYourTurn);
+			ensure!(!Self::is_turn_expired(&game_id)?, <Error<T>>::TurnExpired);
+
+			let moves = Self::game_moves(game_id);
+			let black = moves
+				.first()
+				.map(|first| first.player as usize)
+				.ok_or(<Error<T>>::NotChoosingColor)?;
+			let seat = Self::get_player_index(&game_id, sender)? as usize;
+			let mut players = Self::players(game_id).into_inner();
+			ensure!(black < players.len(), <Error<T>>::InvalidPlayerIndex);
+			if (color == Color::Black) != (seat == black) {
+				players.swap(seat, black);
+			}
+			let next_player = players
+				.get(Self::stone_of_move(black as i8, moves.len()) as usize)
+				.cloned()
+				.ok_or(<Error<T>>::InvalidPlayerIndex)?;
+			let players: BoundedVec<_, _> =
+				players.try_into().map_err(|_| <Error<T>>::PlayersOverflow)?;
+			<Players<T>>::insert(game_id, players);
+
+			<Opening<T>>::remove(game_id);
+			Self::set_turn(&game_id, next_player);
+			Self::deposit_event(Event::ColorChosen(game_id, sender.clone(), color));
 			Ok(())
 		}
 
+		/// The commitment a player makes on join: the hash of their account and secret, so
+		/// it can not be copied by the other player.
+		pub fn commitment_of(player: &T::AccountId, secret: &[u8; 32]) -> T::Hash {
+			T::Hashing::hash_of(&(player, secret))
+		}
+
+		#[transactional]
+		pub fn reveal_secret(sender: &T::AccountId, secret: [u8; 32]) -> Result<(), Error<T>> {
+			let game_id = Self::get_game_playing(sender)?;
+			let deadline = Self::reveal_deadline(game_id).ok_or(<Error<T>>::NotRevealing)?;
+			ensure!(Self::block_number() <= deadline, <Error<T>>::RevealExpired);
+			ensure!(!<Reveals<T>>::contains_key(game_id, sender), <Error<T>>::AlreadyRevealed);
+			let commitment =
+				Self::commitment(game_id, sender).ok_or(<Error<T>>::CommitmentMissing)?;
+			ensure!(Self::commitment_of(sender, &secret) == commitment, <Error<T>>::InvalidReveal);
+			<Reveals<T>>::insert(game_id, sender, secret);
+
+			let active = Self::active_players(&game_id);
+			if active.iter().all(|player| <Reveals<T>>::contains_key(game_id, player)) {
+				Self::pick_first_player(&game_id, &active)?;
+			}
+			Ok(())
+		}
+
+		/// The XOR of the revealed secrets picks who of `revealed` moves first.
+		pub fn pick_first_player(game_id: &ID, revealed: &[T::AccountId]) -> Result<(), Error<T>> {
+			let mut seed = [0u8; 32];
+			for player in revealed {
+				let secret = Self::revealed(game_id, player).ok_or(<Error<T>>::NotRevealing)?;
+				seed.iter_mut().zip(secret.iter()).for_each(|(s, b)| *s ^= b);
+			}
+			let first = revealed
+				.get(seed[0] as usize % revealed.len().max(1))
+				.cloned()
+				.ok_or(<Error<T>>::NotEnoughPlayer)?;
+			Self::clear_commitments(game_id, &Self::players(game_id));
+			Self::set_turn(game_id, first.clone());
+			Self::deposit_event(Event::GameStarted(*game_id, Self::turn_order(game_id, &first)?));
+			Ok(())
+		}
+
+		/// Players still at the table in the order they move, starting with `first`.
+		pub fn turn_order(
+			game_id: &ID,
+			first: &T::AccountId,
+		) -> Result<BoundedVec<T::AccountId, T::MaxGomokuPlayer>, Error<T>> {
+			let mut order = Self::active_players(game_id);
+			let seat = order.iter().position(|p| p == first).ok_or(<Error<T>>::PlayersNotFound)?;
+			order.rotate_left(seat);
+			order.try_into().map_err(|_| <Error<T>>::PlayersOverflow)
+		}
+
+		/// After the reveal deadline, players who did not reveal are out. The game is a
+		/// draw if nobody revealed, won by a single player who did, and otherwise goes on
+		/// among the players who revealed.
+		#[transactional]
+		pub fn settle_reveal(game_id: ID) -> DispatchResult {
+			let deadline = Self::reveal_deadline(game_id).ok_or(<Error<T>>::NotRevealing)?;
+			ensure!(deadline < Self::block_number(), <Error<T>>::RevealNotExpired);
+			let (revealed, missing): (Vec<T::AccountId>, Vec<T::AccountId>) =
+				Self::active_players(&game_id)
+					.into_iter()
+					.partition(|player| <Reveals<T>>::contains_key(game_id, player));
+			let game_map = Self::gomoku_game(game_id).ok_or(<Error<T>>::GameMapNotFound)?;
+			if revealed.is_empty() {
+				return Self::finish_game(GameOutcome::Draw, game_id, game_map)
+			}
+
+			for player in missing.iter() {
+				Self::remove_from_table(&game_id, player)?;
+				Self::deposit_event(Event::PlayerTimedOut(game_id, player.clone()));
+			}
+			match revealed.as_slice() {
+				[winner] =>
+					Self::finish_game(GameOutcome::Timeout(winner.clone()), game_id, game_map),
+				_ => Ok(Self::pick_first_player(&game_id, &revealed)?),
+			}
+		}
+
+		pub fn clear_commitments(game_id: &ID, players: &[T::AccountId]) {
+			for player in players {
+				<Commitments<T>>::remove(game_id, player);
+				<Reveals<T>>::remove(game_id, player);
+			}
+			<RevealDeadline<T>>::remove(game_id);
+		}
+
+		pub fn check_settings(settings: &GameSettings) -> Result<(), Error<T>> {
+			let limits = Self::board_limits();
+			let size = limits.min_size..=limits.max_size;
+			ensure!(
+				size.contains(&settings.width) && size.contains(&settings.height),
+				<Error<T>>::BoardSizeNotAllowed
+			);
+			ensure!(limits.allows(settings), <Error<T>>::WinLengthNotAllowed);
+			ensure!(
+				settings.rule != RuleSet::Renju || settings.win_length == 5,
+				<Error<T>>::WinLengthNotAllowed
+			);
+			ensure!(
+				settings.players >= 2 && settings.players as u32 <= T::MaxGomokuPlayer::get(),
+				<Error<T>>::PlayersNotAllowed
+			);
+			ensure!(
+				settings.players == 2 ||
+					(settings.rule != RuleSet::Renju && settings.opening == OpeningRule::Free),
+				<Error<T>>::RuleNeedsTwoPlayers
+			);
+			Ok(())
+		}
+
+		pub fn record_move(
+			game_id: &ID,
+			player_index: i8,
+			x: usize,
+			y: usize,
+		) -> Result<(), Error<T>> {
+			let new_move = Move {
+				player: player_index as u8,
+				x: x as u8,
+				y: y as u8,
+				block: Self::block_number(),
+			};
+			<GameMoves<T>>::try_mutate(game_id, |moves| moves.try_push(new_move))
+				.map_err(|_| <Error<T>>::MovesOverflow)
+		}
+
+		/// Re-apply `moves` on an empty board through `gomoku_core::play`, so a stored result
+		/// can be verified against its moves. Does not touch storage.
+		///
+		/// Stones have to follow the seat rotation from the first one. A seat passed over is
+		/// out of the game and may not move again, so two players strictly alternate.
+		pub fn replay(
+			settings: &GameSettings,
+			moves: &[Move<T::BlockNumber>],
+		) -> Result<ReplayOutcome, Error<T>> {
+			let mut game_map = Board::new(settings.width, settings.height);
+			let mut outcome = ReplayOutcome::Unfinished;
+			let black = moves.first().map_or(0, |first| first.player as i8);
+			let seats = settings.players as usize;
+			let mut out: Vec<bool> = (0..seats).map(|_| false).collect();
+			let mut previous = None;
+			for (index, m) in moves.iter().enumerate() {
+				ensure!(outcome == ReplayOutcome::Unfinished, <Error<T>>::GameAlreadyEnded);
+				let seat = m.player as usize;
+				ensure!(seat < seats, <Error<T>>::InvalidPlayerIndex);
+				if let Some(previous) = previous {
+					ensure!(seat != previous && !out[seat], <Error<T>>::NotYourTurn);
+					let mut skipped = (previous + 1) % seats;
+					while skipped != seat {
+						out[skipped] = true;
+						skipped = (skipped + 1) % seats;
+					}
+					// the game ends as soon as a single player is left
+					ensure!(out.iter().filter(|o| !**o).count() >= 2, <Error<T>>::NotYourTurn);
+				}
+				previous = Some(seat);
+				let player_index =
+					i8::try_from(m.player).map_err(|_| <Error<T>>::InvalidPlayerIndex)?;
+				let (x, y) = (m.x as usize, m.y as usize);
+				gomoku_core::check_placement(&game_map, x, y).map_err(<Error<T>>::from)?;
+				Self::check_opening_move(settings, &game_map, index, x, y)?;
+				let played = gomoku_core::play(
+					&mut game_map,
+					settings.rule,
+					settings.win_length,
+					black,
+					player_index,
+					x,
+					y,
+				)
+				.map_err(<Error<T>>::from)?;
+				outcome = match played {
+					MoveOutcome::Win => ReplayOutcome::Win(m.player),
+					MoveOutcome::Draw => ReplayOutcome::Draw,
+					MoveOutcome::Continue => ReplayOutcome::Unfinished,
+				};
+			}
+			Ok(outcome)
+		}
+
+		pub fn offer_draw_game(sender: T::AccountId) -> Result<(), Error<T>> {
+			let game_id = Self::get_game_playing(&sender)?;
+			Self::is_game_started(&game_id)?;
+			ensure!(Self::active_players(&game_id).len() == 2, <Error<T>>::DrawNeedsTwoPlayers);
+			<DrawOffer<T>>::insert(game_id, sender);
+			Ok(())
+		}
+
+		#[transactional]
+		pub fn accept_draw_game(sender: &T::AccountId) -> DispatchResult {
+			let game_id = Self::get_game_playing(sender)?;
+			let offered_by = Self::draw_offer(game_id).ok_or(<Error<T>>::DrawNotOffered)?;
+			ensure!(offered_by != *sender, <Error<T>>::CanNotAcceptOwnDraw);
+			let game_map = Self::gomoku_game(game_id).ok_or(<Error<T>>::GameMapNotFound)?;
+			Self::finish_game(GameOutcome::Draw, game_id, game_map)
+		}
+
 		pub fn get_turn(sender: &T::AccountId, game_id: ID) -> Result<bool, Error<T>> {
 			match Self::turn(game_id) {
-				Some(player) =>
-					if *sender == player {
+				Some(turn) =>
+					if *sender == turn.player {
 						Ok(true)
 					} else {
 						Ok(false)
@@ -338,136 +1208,482 @@ pub mod pallet {
 		pub fn continue_game(
 			sender: &T::AccountId,
 			game_id: &ID,
-			x: usize,
-			y: usize,
-			player_index: i8,
+			gomoku_game: Board,
 		) -> Result<(), Error<T>> {
-			<GomokuGame<T>>::try_mutate(game_id, |gomoku| {
-				let mut new_gomoku = gomoku.unwrap();
-				new_gomoku[x][y] = player_index;
-				*gomoku = Some(new_gomoku);
-				Ok(())
-			})
-			.map_err(|_: Error<T>| <Error<T>>::GameMapNotFound)?;
+			<GomokuGame<T>>::insert(game_id, gomoku_game);
+
+			// moving declines any pending draw offer
+			<DrawOffer<T>>::remove(game_id);
+			let next_player = match Self::advance_opening(game_id) {
+				// opening stones are placed by the same player until a color is to be picked
+				Some(OpeningPhase::Tentative) | Some(OpeningPhase::ExtraStones) => sender.clone(),
+				_ => Self::get_next_player(&game_id, sender)?,
+			};
+			Self::set_turn(game_id, next_player);
+			Ok(())
+		}
+
+		/// Hand the turn to `player` with a fresh move deadline.
+		pub fn set_turn(game_id: &ID, player: T::AccountId) {
+			let deadline = Self::block_number().saturating_add(T::MoveTimeout::get());
+			<Turn<T>>::insert(game_id, PlayerTurn { player, deadline });
+		}
+
+		pub fn is_turn_expired(game_id: &ID) -> Result<bool, Error<T>> {
+			let turn = Self::turn(game_id).ok_or(<Error<T>>::PlayerNotPlaying)?;
+			Ok(turn.deadline < Self::block_number())
+		}
+
+		/// A player waiting for an expired turn takes the late player out of the game.
+		#[transactional]
+		pub fn claim_timeout_win(sender: &T::AccountId) -> DispatchResult {
+			let game_id = Self::get_game_playing(sender)?;
+			Self::is_game_started(&game_id)?;
+			if <RevealDeadline<T>>::contains_key(game_id) {
+				return Self::settle_reveal(game_id)
+			}
+			ensure!(!Self::get_turn(sender, game_id)?, <Error<T>>::CanNotClaimOwnTurn);
+			ensure!(Self::is_turn_expired(&game_id)?, <Error<T>>::TurnNotExpired);
+			Self::timeout_game(game_id)
+		}
+
+		#[transactional]
+		pub fn timeout_game(game_id: ID) -> DispatchResult {
+			let turn = Self::turn(game_id).ok_or(<Error<T>>::PlayerNotPlaying)?;
+			Self::deposit_event(Event::PlayerTimedOut(game_id, turn.player.clone()));
+			Self::eliminate_player(game_id, &turn.player, GameOutcome::Timeout)
+		}
 
-			let other_player = Self::get_other_player(&game_id, sender)?;
-			<Turn<T>>::insert(game_id, other_player);
+		/// Take `player` out of a started game. The last player left wins with `outcome`,
+		/// otherwise play goes on and the turn passes on if it was the player's.
+		pub fn eliminate_player(
+			game_id: ID,
+			player: &T::AccountId,
+			outcome: fn(T::AccountId) -> GameOutcome<T::AccountId>,
+		) -> DispatchResult {
+			let next_player = Self::get_next_player(&game_id, player)?;
+			Self::remove_from_table(&game_id, player)?;
+			// a draw offer was made to the players still at the table
+			<DrawOffer<T>>::remove(game_id);
+
+			if let [winner] = Self::active_players(&game_id).as_slice() {
+				let game_map = Self::gomoku_game(game_id).ok_or(<Error<T>>::GameMapNotFound)?;
+				return Self::finish_game(outcome(winner.clone()), game_id, game_map)
+			}
+			if Self::turn(game_id).map_or(false, |turn| turn.player == *player) {
+				Self::set_turn(&game_id, next_player);
+			}
 			Ok(())
 		}
 
+		/// The player keeps the seat, so the stones stay theirs, but takes no more turns and
+		/// is free to join another game. The ticket stays in the pot.
+		pub fn remove_from_table(game_id: &ID, player: &T::AccountId) -> Result<(), Error<T>> {
+			<Eliminated<T>>::try_mutate(game_id, |out| out.try_push(player.clone()))
+				.map_err(|_| <Error<T>>::PlayersOverflow)?;
+			<GamePlaying<T>>::remove(player);
+			Ok(())
+		}
+
+		/// Players of a started game who have not left or timed out, in join order.
+		pub fn active_players(game_id: &ID) -> Vec<T::AccountId> {
+			let eliminated = Self::eliminated(game_id);
+			Self::players(game_id).into_iter().filter(|p| !eliminated.contains(p)).collect()
+		}
+
+		#[transactional]
+		pub fn expire_open_game(game_id: ID) -> DispatchResult {
+			let game = Self::get_game(&game_id)?;
+			Self::cancel_open_game(&game)
+		}
+
+		/*
+			1. Started games whose turn deadline passed lose the late player
+			2. Open games past their expiry are cancelled and every ticket refunded
+			Each lobby is swept from where the last block stopped, so every game gets looked
+			at even when the weight only fits a few per block. Stops as soon as the next game
+			may not fit in the remaining weight.
+		*/
+		pub fn settle_expired_games(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db_weight = T::DbWeight::get();
+			let check_weight = db_weight.reads(3);
+			// a late move or a reveal settles like a claimed timeout, an expiry like a cancel
+			let settle_weight = T::WeightInfo::claim_timeout().max(T::WeightInfo::cancel());
+			let mut used_weight = db_weight.reads_writes(4, 2);
+			if used_weight > remaining_weight {
+				return 0
+			}
+
+			for lobby in [Lobby::Started, Lobby::Open] {
+				let mut slot = Self::lobby_cursor(lobby);
+				for _ in 0..Self::lobby_size(lobby) {
+					if used_weight.saturating_add(check_weight).saturating_add(settle_weight) >
+						remaining_weight
+					{
+						<LobbyCursor<T>>::insert(lobby, slot);
+						return used_weight
+					}
+					used_weight = used_weight.saturating_add(check_weight);
+					// settled games leave the lobby, so its end moves
+					if slot >= Self::lobby_size(lobby) {
+						slot = 0;
+					}
+					let game_id = match Self::lobby_game(lobby, slot) {
+						Some(game_id) => game_id,
+						None => break,
+					};
+					match Self::settle_expired_game(lobby, game_id, now) {
+						Ok(false) => {},
+						Ok(true) => used_weight = used_weight.saturating_add(settle_weight),
+						// rolled back, but the work was done, the game is retried next sweep
+						Err(error) => {
+							log::error!(
+								target: "runtime::gomoku",
+								"failed to settle expired game {:?}: {:?}",
+								game_id,
+								error,
+							);
+							used_weight = used_weight.saturating_add(settle_weight);
+						},
+					}
+					// a game leaving the lobby hands its slot to the last one
+					if Self::lobby_game(lobby, slot) == Some(game_id) {
+						slot = slot.saturating_add(1);
+					}
+				}
+				<LobbyCursor<T>>::insert(lobby, slot);
+			}
+			used_weight
+		}
+
+		/// Settle `game_id` if its deadline passed, returns whether it was settled.
+		pub fn settle_expired_game(
+			lobby: Lobby,
+			game_id: ID,
+			now: T::BlockNumber,
+		) -> Result<bool, DispatchError> {
+			match lobby {
+				Lobby::Started =>
+					if let Some(turn) = Self::turn(game_id) {
+						if turn.deadline < now && Self::is_game_started(&game_id).is_ok() {
+							return Self::timeout_game(game_id).map(|_| true)
+						}
+					} else if let Some(deadline) = Self::reveal_deadline(game_id) {
+						if deadline < now {
+							return Self::settle_reveal(game_id).map(|_| true)
+						}
+					},
+				Lobby::Open =>
+					if let Some(game) = Self::games(game_id) {
+						if game.expire < now && game.status == GameStatus::Open {
+							return Self::expire_open_game(game_id).map(|_| true)
+						}
+					},
+			}
+			Ok(false)
+		}
+
+		/*
+			Ended games past `EndedGameRetention` are removed oldest first, along with their
+			game, board and moves. Stops as soon as the next one may not fit in the remaining
+			weight.
+		*/
+		pub fn prune_ended_games(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db_weight = T::DbWeight::get();
+			let prune_weight = db_weight.reads_writes(2, 6);
+			let mut used_weight = db_weight.reads_writes(2, 1);
+			if used_weight > remaining_weight {
+				return 0
+			}
+
+			let tail = Self::ended_tail();
+			let mut head = Self::ended_head();
+			while head < tail && used_weight.saturating_add(prune_weight) <= remaining_weight {
+				used_weight = used_weight.saturating_add(prune_weight);
+				if let Some(game_id) = Self::ended_game_at(head) {
+					// games are queued as they end, the rest of the queue is newer
+					let kept = Self::ended_game(game_id)
+						.map_or(false, |game| now < Self::prunable_at(game.ended_at));
+					if kept {
+						break
+					}
+					Self::prune_ended_game(head, game_id);
+				}
+				head = head.saturating_add(1);
+			}
+			<EndedHead<T>>::put(head);
+			used_weight
+		}
+
+		/// First block an ended game may be pruned in, once `EndedGameRetention` blocks passed.
+		pub fn prunable_at(ended_at: T::BlockNumber) -> T::BlockNumber {
+			ended_at.saturating_add(T::EndedGameRetention::get()).saturating_add(One::one())
+		}
+
+		pub fn prune_ended_game(seq: u64, game_id: ID) {
+			<EndedGameQueue<T>>::remove(seq);
+			<EndedGames<T>>::remove(game_id);
+			<Games<T>>::remove(game_id);
+			<GameMoves<T>>::remove(game_id);
+			<GomokuGame<T>>::remove(game_id);
+			Self::deposit_event(Event::EndedGamePruned(game_id));
+		}
+
 		pub fn finish_game(
-			winner: T::AccountId,
+			outcome: GameOutcome<T::AccountId>,
 			game_id: ID,
-			game_map: [[i8; 15]; 15],
-		) -> Result<(), Error<T>> {
-			let game = Self::get_game(&game_id)?;
+			game_map: Board,
+		) -> DispatchResult {
+			let game = Self::set_game_status(&game_id, GameStatus::End)?;
 			let players = Self::players(game_id);
-			for player in players {
+			// eliminated players left the table already and may be in another game
+			let active = Self::active_players(&game_id);
+			for player in active.iter() {
 				<GamePlaying<T>>::remove(player);
 			}
+			<Eliminated<T>>::remove(game_id);
+			Self::clear_commitments(&game_id, &players);
 			<Players<T>>::remove(game_id);
 			<GameHosting<T>>::remove(game.host.clone());
-			<GameStart<T>>::try_mutate(|id_vec| {
-				if let Some(ind) = id_vec.iter().position(|&id| id == game_id) {
-					id_vec.swap_remove(ind);
-					return Ok(())
-				}
-				Err(())
-			})
-			.map_err(|_| <Error<T>>::GameStartNotFound)?;
+			Self::unlist_game(Lobby::Started, &game_id)?;
 			<Turn<T>>::remove(game_id);
+			<DrawOffer<T>>::remove(game_id);
+			<Opening<T>>::remove(game_id);
 
 			let ended_game = EndedGame {
 				id: game.id,
 				host: game.host,
 				ticket: game.ticket,
-				winner: winner.clone(),
+				outcome: outcome.clone(),
 				game_map,
+				players: players.clone(),
+				ended_at: Self::block_number(),
 			};
 
-			<GetEndedGames<T>>::try_mutate(|game_vec| game_vec.try_push(game_id))
-				.map_err(|_| <Error<T>>::GameEndedNotFound)?;
-
+			let seq = Self::ended_tail();
+			<EndedTail<T>>::put(seq.checked_add(1).ok_or(<Error<T>>::GameOverflow)?);
+			<EndedGameQueue<T>>::insert(seq, game_id);
 			<EndedGames<T>>::insert(game_id, ended_game);
-			let ticket = Self::balance_to_u64(game.ticket).unwrap();
-			let reward = (ticket * 2) as f64 - ((ticket * 2) as f64 * 0.01);
-			let ticket = Self::u64_to_balance(reward as u64).unwrap();
-			let _ = T::Currency::deposit_into_existing(&winner, ticket);
+			let pot = game.ticket.saturating_mul((players.len() as u32).into());
+			let (reward, rake) = Self::split_pot(pot);
+			let (paid, rake) = match outcome.winner() {
+				Some(winner) => {
+					Self::payout(winner, reward)?;
+					(reward, rake)
+				},
+				// the players still at the table share the pot
+				None => {
+					let count: BalanceOf<T> = (active.len() as u32).into();
+					let share = reward.checked_div(&count).ok_or(<Error<T>>::NotEnoughPlayer)?;
+					for player in active.iter() {
+						Self::payout(player, share)?;
+					}
+					// shares are rounded down, the dust goes with the rake
+					let paid = share.saturating_mul(count);
+					(paid, rake.saturating_add(reward.saturating_sub(paid)))
+				},
+			};
+			let rake = Self::collect_rake(rake);
+
+			let winner = outcome.winner().cloned();
+			let prunable_at = Self::prunable_at(Self::block_number());
+			Self::deposit_event(Event::GameEnded(
+				game_id,
+				outcome,
+				winner,
+				paid,
+				rake,
+				prunable_at,
+			));
 			Ok(())
 		}
 
 		#[transactional]
-		pub fn join_game(sender: T::AccountId, game_id: &ID) -> Result<Game<T>, Error<T>> {
+		pub fn join_game(
+			sender: T::AccountId,
+			game_id: &ID,
+			commitment: Option<T::Hash>,
+		) -> Result<Game<T>, Error<T>> {
 			// make sure game id exsit
 			let game = Self::get_game(game_id)?;
 			Self::is_game_open(game_id)?;
 
 			// make sute player not playing
 			Self::is_player_available(&sender)?;
-			Self::player_join_game(sender, game_id)?;
+			match (game.settings.commit_reveal, commitment) {
+				(true, None) => return Err(<Error<T>>::CommitmentMissing),
+				(false, Some(_)) => return Err(<Error<T>>::CommitmentNotExpected),
+				_ => (),
+			}
+			Self::player_join_game(sender.clone(), &game)?;
+			if let Some(commitment) = commitment {
+				<Commitments<T>>::insert(game_id, sender, commitment);
+			}
 			Ok(game)
 		}
 
-		pub fn player_join_game(sender: T::AccountId, game_id: &ID) -> Result<(), Error<T>> {
-			let players = <Players<T>>::get(game_id);
-			ensure!((players.len() as u8) < Self::max_gomoku_player(), <Error<T>>::PlayerExceed);
-			<Players<T>>::try_mutate(game_id, |player_vec| player_vec.try_push(sender.clone()))
+		pub fn player_join_game(sender: T::AccountId, game: &Game<T>) -> Result<(), Error<T>> {
+			let players = <Players<T>>::get(game.id);
+			ensure!(players.len() < game.settings.players as usize, <Error<T>>::PlayerExceed);
+			<Players<T>>::try_mutate(game.id, |player_vec| player_vec.try_push(sender.clone()))
 				.map_err(|_| <Error<T>>::PlayersOverflow)?;
-			<GamePlaying<T>>::insert(sender, game_id);
+			<GamePlaying<T>>::insert(sender, game.id);
 			Ok(())
 		}
 
-		pub fn player_left_game(sender: T::AccountId, game: &Game<T>) -> Result<(), Error<T>> {
-			let game_id = Self::get_game_playing(&sender)?;
-			let game = Self::get_game(&game_id)?;
+		pub fn player_left_game(sender: T::AccountId, game: &Game<T>) -> DispatchResult {
+			<Players<T>>::try_mutate(game.id, |player_vec| {
+				if let Some(ind) = player_vec.iter().position(|p| *p == sender) {
+					player_vec.remove(ind);
+					return Ok(())
+				}
+				Err(())
+			})
+			.map_err(|_| <Error<T>>::PlayersNotFound)?;
+			<GamePlaying<T>>::remove(&sender);
+			<Commitments<T>>::remove(game.id, &sender);
+			Self::deposit_event(Event::PlayerLeft(game.id, sender.clone()));
+			Self::refund_ticket(&game.id, &sender, game.ticket)?;
+			Ok(())
+		}
 
+		/// Tear down an open game: every joined player gets the ticket back and the game is
+		/// removed from the lobby and from storage.
+		pub fn cancel_open_game(game: &Game<T>) -> DispatchResult {
+			Self::set_game_status(&game.id, GameStatus::Cancel)?;
+			let players = Self::players(game.id);
+			Self::clear_commitments(&game.id, &players);
+			for player in players {
+				<GamePlaying<T>>::remove(&player);
+				Self::refund_ticket(&game.id, &player, game.ticket)?;
+			}
+			<Players<T>>::remove(game.id);
+			Self::unlist_game(Lobby::Open, &game.id)?;
+			<GameHosting<T>>::remove(&game.host);
+			<Games<T>>::remove(game.id);
+			Self::deposit_event(Event::GameCancelled(game.id));
 			Ok(())
 		}
 
 		#[transactional]
-		pub fn left_game(sender: T::AccountId) -> Result<(), Error<T>> {
-			let game_id = Self::get_game_playing(&sender)?;
+		pub fn cancel_game(sender: &T::AccountId) -> DispatchResult {
+			let game_id = Self::game_hosting(sender).ok_or(<Error<T>>::NotGameHost)?;
+			let game = Self::get_game(&game_id)?;
+			ensure!(game.status == GameStatus::Open, <Error<T>>::GameNotOpen);
+			Self::cancel_open_game(&game)
+		}
+
+		/*
+			1. Open game: the player gets the ticket back, the host tears the game down
+			2. Started game: the player forfeits, the last player left wins the pot
+		*/
+		#[transactional]
+		pub fn left_game(sender: T::AccountId) -> DispatchResult {
+			// the host may have opened the game without joining it
+			let game_id = match Self::game_playing(&sender) {
+				Some(id) => id,
+				None => Self::get_game_hosting(&sender)?,
+			};
 			let game = Self::get_game(&game_id)?;
 
-			// make sure game status is open
+			match game.status {
+				GameStatus::Start => {
+					// an eliminated host still hosts the game but no longer plays it
+					ensure!(
+						Self::active_players(&game_id).contains(&sender),
+						<Error<T>>::PlayerNotPlaying
+					);
+					Self::deposit_event(Event::PlayerLeft(game_id, sender.clone()));
+					Self::eliminate_player(game_id, &sender, GameOutcome::Forfeit)?;
+				},
+				GameStatus::Open if game.host == sender => Self::cancel_open_game(&game)?,
+				GameStatus::Open => Self::player_left_game(sender, &game)?,
+				GameStatus::End | GameStatus::Cancel =>
+					return Err(<Error<T>>::PlayerNotPlaying.into()),
+			}
+			Ok(())
+		}
 
-			let players = <Players<T>>::get(game_id);
+		/// The account holding every ticket and open fee until the game is settled.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
 
+		pub fn refund_ticket(
+			game_id: &ID,
+			player: &T::AccountId,
+			ticket: BalanceOf<T>,
+		) -> DispatchResult {
+			Self::payout(player, ticket)?;
+			Self::deposit_event(Event::Refunded(*game_id, player.clone(), ticket));
 			Ok(())
 		}
 
-		pub fn charge_join_game(sender: &T::AccountId, ticket: BalanceOf<T>) -> DispatchResult {
-			let withdraw = T::Currency::withdraw(
-				&sender,
-				ticket,
-				WithdrawReasons::RESERVE,
+		/// Pay out of the escrow, the escrow account itself is never reaped.
+		pub fn payout(player: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			T::Currency::transfer(
+				&Self::account_id(),
+				player,
+				amount,
 				ExistenceRequirement::KeepAlive,
-			);
+			)
+		}
+
+		/// Split a pot into the payout and the house rake, the rake is rounded down.
+		pub fn split_pot(pot: BalanceOf<T>) -> (BalanceOf<T>, BalanceOf<T>) {
+			let rake = Self::house_rake().mul_floor(pot);
+			(pot.saturating_sub(rake), rake)
+		}
+
+		/// The account collecting the rake and open fees.
+		pub fn fee_account() -> T::AccountId {
+			Self::fee_recipient().unwrap_or_else(Self::account_id)
+		}
 
-			match withdraw {
-				Ok(_) => Ok(()),
-				Err(err) => Err(err),
+		/// Move the rake to the fee recipient and return the amount moved. A rake the escrow
+		/// cannot transfer, e.g. too small to create the recipient account, stays in the
+		/// escrow rather than blocking the payout.
+		pub fn collect_rake(rake: BalanceOf<T>) -> BalanceOf<T> {
+			match T::Currency::transfer(
+				&Self::account_id(),
+				&Self::fee_account(),
+				rake,
+				ExistenceRequirement::KeepAlive,
+			) {
+				Ok(()) => rake,
+				Err(_) => Zero::zero(),
 			}
 		}
 
+		pub fn charge_join_game(sender: &T::AccountId, ticket: BalanceOf<T>) -> DispatchResult {
+			T::Currency::transfer(
+				sender,
+				&Self::account_id(),
+				ticket,
+				ExistenceRequirement::KeepAlive,
+			)
+		}
+
 		pub fn charge_fee_open_game(sender: &T::AccountId) -> DispatchResult {
-			let open_game_fee = Self::open_game_fee();
-			let withdraw = T::Currency::withdraw(
-				&sender,
-				open_game_fee,
-				WithdrawReasons::RESERVE,
+			T::Currency::transfer(
+				sender,
+				&Self::fee_account(),
+				Self::open_game_fee(),
 				ExistenceRequirement::KeepAlive,
-			);
+			)
+		}
 
-			match withdraw {
-				Ok(_) => Ok(()),
-				Err(err) => Err(err),
-			}
+		pub fn block_number() -> T::BlockNumber {
+			<frame_system::Pallet<T>>::block_number()
 		}
 
 		pub fn gen_id() -> Result<ID, Error<T>> {
-			let payload =
-				(T::GameRandomness::random(&b""[..]).0, <frame_system::Pallet<T>>::block_number());
+			// the counter tells apart games opened in the same block
+			let payload = (
+				T::GameRandomness::random(&b""[..]).0,
+				<frame_system::Pallet<T>>::block_number(),
+				Self::game_cnt(),
+			);
 			Ok(payload.using_encoded(blake2_256))
 		}
 
@@ -499,6 +1715,13 @@ pub mod pallet {
 			}
 		}
 
+		pub fn get_game_hosting(player: &T::AccountId) -> Result<ID, Error<T>> {
+			match Self::game_hosting(player) {
+				Some(id) => Ok(id),
+				None => Err(<Error<T>>::PlayerNotPlaying),
+			}
+		}
+
 		pub fn get_game_playing(player: &T::AccountId) -> Result<ID, Error<T>> {
 			match Self::game_playing(player) {
 				Some(id) => Ok(id),
@@ -507,163 +1730,308 @@ pub mod pallet {
 		}
 
 		pub fn is_game_open(game_id: &ID) -> Result<bool, Error<T>> {
-			match <GameOpen<T>>::get().binary_search(game_id) {
-				Ok(_) => Ok(true),
-				Err(_) => Err(<Error<T>>::GameOpenNotFound),
+			match Self::get_game(game_id)?.status {
+				GameStatus::Open => Ok(true),
+				_ => Err(<Error<T>>::GameNotOpen),
+			}
+		}
+
+		pub fn is_game_started(game_id: &ID) -> Result<bool, Error<T>> {
+			match Self::get_game(game_id)?.status {
+				GameStatus::Start => Ok(true),
+				_ => Err(<Error<T>>::GameNotStarted),
 			}
 		}
 
+		/// Move a game to `status`, rejecting transitions the lifecycle does not allow.
+		pub fn set_game_status(game_id: &ID, status: GameStatus) -> Result<Game<T>, Error<T>> {
+			<Games<T>>::try_mutate(game_id, |maybe_game| {
+				let game = maybe_game.as_mut().ok_or(<Error<T>>::GameNotExist)?;
+				ensure!(
+					game.status.can_transition_to(&status),
+					<Error<T>>::InvalidStatusTransition
+				);
+				game.status = status;
+				Ok(game.clone())
+			})
+		}
+
+		/// Add a game to the end of `lobby`.
+		pub fn list_game(lobby: Lobby, game_id: ID) -> Result<(), Error<T>> {
+			ensure!(!<LobbySlots<T>>::contains_key(game_id), <Error<T>>::GameIdUsed);
+			let slot = Self::lobby_size(lobby);
+			let size = slot.checked_add(1).ok_or(<Error<T>>::GameOverflow)?;
+			<LobbyGames<T>>::insert(lobby, slot, game_id);
+			<LobbySlots<T>>::insert(game_id, (lobby, slot));
+			<LobbySize<T>>::insert(lobby, size);
+			Ok(())
+		}
+
+		/// Take a game out of `lobby`, the last game of the lobby moves into its slot.
+		pub fn unlist_game(lobby: Lobby, game_id: &ID) -> Result<(), Error<T>> {
+			let not_found = || match lobby {
+				Lobby::Open => <Error<T>>::GameOpenNotFound,
+				Lobby::Started => <Error<T>>::GameStartNotFound,
+			};
+			let slot = match Self::lobby_slot(game_id) {
+				Some((listed, slot)) if listed == lobby => slot,
+				_ => return Err(not_found()),
+			};
+			let last = Self::lobby_size(lobby).checked_sub(1).ok_or_else(not_found)?;
+			if slot != last {
+				let moved = Self::lobby_game(lobby, last).ok_or_else(not_found)?;
+				<LobbyGames<T>>::insert(lobby, slot, moved);
+				<LobbySlots<T>>::insert(moved, (lobby, slot));
+			}
+			<LobbyGames<T>>::remove(lobby, last);
+			<LobbySlots<T>>::remove(game_id);
+			<LobbySize<T>>::insert(lobby, last);
+			Ok(())
+		}
+
+		pub fn is_listed(lobby: Lobby, game_id: &ID) -> bool {
+			matches!(Self::lobby_slot(game_id), Some((listed, _)) if listed == lobby)
+		}
+
+		/// Games of `lobby` in slot order, `LOBBY_PAGE_SIZE` per page.
+		pub fn lobby_page(lobby: Lobby, page: u32) -> Vec<ID> {
+			let start = page.saturating_mul(LOBBY_PAGE_SIZE);
+			let end = start.saturating_add(LOBBY_PAGE_SIZE).min(Self::lobby_size(lobby));
+			(start..end).filter_map(|slot| Self::lobby_game(lobby, slot)).collect()
+		}
+
+		pub fn open_games(page: u32) -> Vec<ID> {
+			Self::lobby_page(Lobby::Open, page)
+		}
+
+		pub fn started_games(page: u32) -> Vec<ID> {
+			Self::lobby_page(Lobby::Started, page)
+		}
+
 		pub fn get_player_index(game_id: &ID, player: &T::AccountId) -> Result<i8, Error<T>> {
-			let slice = Self::players(game_id);
-			let index = slice.iter().position(|p| p == player).unwrap();
-			Ok(index as i8)
+			let index = Self::players(game_id)
+				.iter()
+				.position(|p| p == player)
+				.ok_or(<Error<T>>::PlayersNotFound)?;
+			i8::try_from(index).map_err(|_| <Error<T>>::InvalidPlayerIndex)
 		}
 
-		pub fn get_other_player(
+		/// The next player in join order after `player` who is still at the table.
+		pub fn get_next_player(
 			game_id: &ID,
 			player: &T::AccountId,
 		) -> Result<T::AccountId, Error<T>> {
-			let slice = Self::players(game_id);
-			let other_player = slice.iter().position(|p| p != player).unwrap();
-			let other = &slice[other_player];
-			Ok(other.clone())
+			let players = Self::players(game_id);
+			let eliminated = Self::eliminated(game_id);
+			let seat =
+				players.iter().position(|p| p == player).ok_or(<Error<T>>::PlayersNotFound)?;
+			(1..players.len())
+				.filter_map(|step| players.get((seat + step) % players.len()))
+				.find(|p| !eliminated.contains(p))
+				.cloned()
+				.ok_or(<Error<T>>::NotEnoughPlayer)
 		}
+	}
 
-		pub fn check_winner(
-			game_map: [[i8; 15]; 15],
-			player_index: i8,
-			x: usize,
-			y: usize,
-		) -> Result<bool, Error<T>> {
-			if x >= 15 || y >= 15 {
-				return Err(<Error<T>>::PlaceNotCorrect)
-			}
-
-			// check horizontal
-			let horizontal_check = || -> bool {
-				let mut count = 0;
-				let mut up = true;
-				let mut down = true;
-				for index in 1..6 {
-					if (x + index < 15) && game_map[x + index][y] == player_index && up {
-						count = count + 1;
-					} else {
-						up = false;
-					}
-					if x >= index && game_map[x - index][y] == player_index && down {
-						count = count + 1;
-					} else {
-						down = false;
-					}
-				}
-				if count >= 4 {
-					return true
-				} else {
-					return false
-				}
-			};
+	// Queries backing the `GomokuApi` runtime API.
+	impl<T: Config> Pallet<T> {
+		/// Open games, `LOBBY_PAGE_SIZE` per page.
+		pub fn open_game_infos(page: u32) -> Vec<GameInfoOf<T>> {
+			Self::open_games(page).iter().filter_map(Self::game_info).collect()
+		}
 
-			let vertical_check = || -> bool {
-				let mut count = 0;
-				let mut up = true;
-				let mut down = true;
-				for index in 1..6 {
-					if (y + index < 15) && game_map[x][y + index] == player_index && up {
-						count = count + 1;
-					} else {
-						up = false;
-					}
-					if y > index && game_map[x][y - index] == player_index && down {
-						count = count + 1;
-					} else {
-						down = false;
-					}
-				}
-				if count >= 4 {
-					return true
-				} else {
-					return false
-				}
-			};
+		pub fn game_info(game_id: &ID) -> Option<GameInfoOf<T>> {
+			let game = Self::games(game_id)?;
+			Some(GameInfo {
+				id: game.id,
+				host: game.host,
+				ticket: game.ticket,
+				expire: game.expire,
+				status: game.status,
+				settings: game.settings,
+				players: Self::players(game_id).into_inner(),
+				eliminated: Self::eliminated(game_id).into_inner(),
+			})
+		}
 
-			let topright_bottomleft_check = || -> bool {
-				let mut count = 0;
-				let mut up = true;
-				let mut down = true;
-				for index in 1..6 {
-					if (x + index < 15) &&
-						(y + index < 15) && game_map[x + index][y + index] == player_index &&
-						up
-					{
-						count = count + 1;
-					} else {
-						up = false;
-					}
-					if x > index &&
-						y > index && game_map[x - index][y - index] == player_index &&
-						down
-					{
-						count = count + 1;
-					} else {
-						down = false;
-					}
-				}
-				if count >= 4 {
-					return true
-				} else {
-					return false
-				}
-			};
+		/// The final board of an ended game, otherwise the board being played.
+		pub fn board_of(game_id: &ID) -> Option<Board> {
+			match Self::ended_game(game_id) {
+				Some(game) => Some(game.game_map),
+				None => Self::gomoku_game(game_id),
+			}
+		}
 
-			let topleft_bottomright_check = || -> bool {
-				let mut count = 0;
-				let mut up = true;
-				let mut down = true;
-				for index in 1..6 {
-					if (x > index) &&
-						(y + index < 15) && game_map[x - index][y + index] == player_index &&
-						up
-					{
-						count = count + 1;
-					} else {
-						up = false;
-					}
-					if (x + index < 15) &&
-						y > index && game_map[x + index][y - index] == player_index &&
-						down
-					{
-						count = count + 1;
-					} else {
-						down = false;
-					}
-				}
-				if count >= 4 {
-					return true
-				} else {
-					return false
-				}
-			};
+		/// Player to move and the last block they can move in.
+		pub fn current_turn(game_id: &ID) -> Option<(T::AccountId, T::BlockNumber)> {
+			Self::turn(game_id).map(|turn| (turn.player, turn.deadline))
+		}
 
-			if horizontal_check() ||
-				vertical_check() || topright_bottomleft_check() ||
-				topleft_bottomright_check()
-			{
-				Ok(true)
-			} else {
-				Ok(false)
+		/// The game `player` is seated at or hosting.
+		pub fn game_of(player: &T::AccountId) -> Option<ID> {
+			Self::game_playing(player).or_else(|| Self::game_hosting(player))
+		}
+
+		/// Ended games, most recent first, `ENDED_GAMES_PAGE_SIZE` per page.
+		pub fn ended_game_infos(page: u32) -> Vec<EndedGameInfoOf<T>> {
+			let newest = Self::ended_tail()
+				.saturating_sub((page as u64).saturating_mul(ENDED_GAMES_PAGE_SIZE));
+			let oldest = newest.saturating_sub(ENDED_GAMES_PAGE_SIZE).max(Self::ended_head());
+			(oldest..newest)
+				.rev()
+				.filter_map(Self::ended_game_at)
+				.filter_map(Self::ended_game)
+				.map(|game| EndedGameInfo {
+					id: game.id,
+					host: game.host,
+					ticket: game.ticket,
+					board: game.game_map,
+					outcome: game.outcome,
+					players: game.players.into_inner(),
+					ended_at: game.ended_at,
+				})
+				.collect()
+		}
+	}
+
+	// Storage invariants, checked after every test and on runtime upgrades under try-runtime.
+	#[cfg(any(test, feature = "try-runtime"))]
+	impl<T: Config> Pallet<T> {
+		pub fn do_try_state() -> Result<(), &'static str> {
+			Self::try_state_lobbies()?;
+			Self::try_state_games()?;
+			Self::try_state_seats()?;
+			Self::try_state_ended_games()
+		}
+
+		/// Every lobby slot below the lobby size holds a game that points back at the slot.
+		fn try_state_lobbies() -> Result<(), &'static str> {
+			let mut listed = 0u32;
+			for lobby in [Lobby::Open, Lobby::Started] {
+				let size = Self::lobby_size(lobby);
+				ensure!(
+					<LobbyGames<T>>::iter_prefix(lobby).count() == size as usize,
+					"lobby size does not match its listed games"
+				);
+				for slot in 0..size {
+					let game_id = Self::lobby_game(lobby, slot).ok_or("lobby slot is empty")?;
+					ensure!(
+						Self::lobby_slot(game_id) == Some((lobby, slot)),
+						"listed game does not point back at its lobby slot"
+					);
+				}
+				listed = listed.saturating_add(size);
 			}
+			ensure!(
+				<LobbySlots<T>>::iter().count() == listed as usize,
+				"lobby slot of a game that is not listed"
+			);
+			Ok(())
 		}
 
-		pub fn balance_to_u64(balance: BalanceOf<T>) -> Option<u64> {
-			TryInto::<u64>::try_into(balance).ok()
+		/// Open games are listed as open and started games as started, both with their host
+		/// hosting them. Only started games have a board and someone to move or reveal.
+		fn try_state_games() -> Result<(), &'static str> {
+			for (game_id, game) in <Games<T>>::iter() {
+				let live = match game.status {
+					GameStatus::Open => {
+						ensure!(Self::is_listed(Lobby::Open, &game_id), "open game is not listed");
+						true
+					},
+					GameStatus::Start => {
+						ensure!(
+							Self::is_listed(Lobby::Started, &game_id),
+							"started game is not listed"
+						);
+						ensure!(
+							<GomokuGame<T>>::contains_key(game_id),
+							"started game has no board"
+						);
+						ensure!(
+							<Turn<T>>::contains_key(game_id) ||
+								<RevealDeadline<T>>::contains_key(game_id),
+							"started game has nobody to move"
+						);
+						true
+					},
+					GameStatus::End => {
+						ensure!(<EndedGames<T>>::contains_key(game_id), "ended game is not kept");
+						false
+					},
+					GameStatus::Cancel => return Err("cancelled game is kept"),
+				};
+				ensure!(
+					live == <LobbySlots<T>>::contains_key(game_id),
+					"settled game is still listed"
+				);
+				ensure!(
+					live == (Self::game_hosting(&game.host) == Some(game_id)),
+					"host of a live game is not hosting it"
+				);
+			}
+			for (host, game_id) in <GameHosting<T>>::iter() {
+				let game = Self::games(game_id).ok_or("hosted game does not exist")?;
+				ensure!(game.host == host, "hosted game has another host");
+			}
+			Ok(())
 		}
 
-		pub fn u64_to_balance(num: u64) -> Option<BalanceOf<T>> {
-			num.try_into().ok()
+		/// Every player still at a live table is playing it and nobody plays anywhere else.
+		/// Turns and draw offers belong to players still at the table.
+		fn try_state_seats() -> Result<(), &'static str> {
+			let is_live = |game_id: &ID| {
+				matches!(
+					Self::games(game_id).map(|game| game.status),
+					Some(GameStatus::Open) | Some(GameStatus::Start)
+				)
+			};
+			let mut seated = 0usize;
+			for (game_id, players) in <Players<T>>::iter() {
+				ensure!(is_live(&game_id), "players seated at a settled game");
+				let eliminated = Self::eliminated(game_id);
+				ensure!(
+					eliminated.iter().all(|player| players.contains(player)),
+					"eliminated player was never seated"
+				);
+				for player in players.iter().filter(|player| !eliminated.contains(player)) {
+					ensure!(
+						Self::game_playing(player) == Some(game_id),
+						"seated player is not playing the game"
+					);
+					seated = seated.saturating_add(1);
+				}
+			}
+			ensure!(
+				<GamePlaying<T>>::iter().count() == seated,
+				"player is playing a game without a seat"
+			);
+			for (game_id, turn) in <Turn<T>>::iter() {
+				ensure!(
+					Self::active_players(&game_id).contains(&turn.player),
+					"turn of a player who is not at the table"
+				);
+			}
+			for (game_id, offered_by) in <DrawOffer<T>>::iter() {
+				ensure!(
+					Self::active_players(&game_id).contains(&offered_by),
+					"draw offered by a player who is not at the table"
+				);
+			}
+			Ok(())
 		}
 
-		pub fn set_max_player(num: u8) -> Result<(), Error<T>> {
-			<MaxGomoku<T>>::put(num);
+		/// The queue holds exactly the kept ended games, between its head and tail.
+		fn try_state_ended_games() -> Result<(), &'static str> {
+			let (head, tail) = (Self::ended_head(), Self::ended_tail());
+			ensure!(head <= tail, "ended game queue head is past its tail");
+			let mut queued = 0usize;
+			for (seq, game_id) in <EndedGameQueue<T>>::iter() {
+				ensure!((head..tail).contains(&seq), "ended game queued outside head and tail");
+				let ended = Self::ended_game(game_id).ok_or("queued game is not kept")?;
+				ensure!(ended.id == game_id, "queued game is kept under another id");
+				queued = queued.saturating_add(1);
+			}
+			ensure!(<EndedGames<T>>::iter().count() == queued, "kept ended game is not queued");
 			Ok(())
 		}
 	}
@@ -0,0 +1,176 @@
+//! Storage migrations of the gomoku pallet.
+
+use crate::{
+	board::{Board, EMPTY},
+	BalanceOf, Config, EndedGame, EndedGameQueue, EndedGames, EndedTail, Game, GameOutcome,
+	GameSettings, GameStatus, Games, GomokuGame, HouseRake, Lobby, Pallet, PlayerTurn, Players,
+	Turn,
+};
+use codec::Decode;
+use frame_support::{
+	migration::take_storage_value,
+	sp_runtime::{
+		traits::{Saturating, Zero},
+		Perbill,
+	},
+	sp_std::vec::Vec,
+	traits::{Currency, Get, PalletInfoAccess, StorageVersion},
+	weights::Weight,
+	BoundedVec,
+};
+
+/// Moves a chain of the first release, which never set a storage version, to version 1.
+pub mod v1 {
+	use super::*;
+
+	type ID = [u8; 32];
+
+	/// `Game` before it had a status, settings and expiry.
+	#[derive(Decode)]
+	struct OldGame<AccountId, Balance> {
+		id: ID,
+		host: AccountId,
+		ticket: Balance,
+	}
+
+	/// Board of the first release, 15 by 15 cells indexed `[x][y]`.
+	type OldBoard = [[i8; 15]; 15];
+
+	/// `EndedGame` before it kept the outcome, the seats and the block it ended in.
+	#[derive(Decode)]
+	struct OldEndedGame<AccountId, Balance> {
+		id: ID,
+		host: AccountId,
+		ticket: Balance,
+		game_map: OldBoard,
+		winner: AccountId,
+	}
+
+	fn packed(cells: OldBoard) -> Board {
+		let mut board = Board::new(15, 15);
+		for (x, column) in cells.iter().enumerate() {
+			for (y, stone) in column.iter().enumerate().filter(|(_, stone)| **stone != EMPTY) {
+				board.set(x, y, *stone);
+			}
+		}
+		board
+	}
+
+	/// Open games go from the `GameOpen` list and started ones from the `GameStart` list into
+	/// the lobbies, and `MaxGomoku` becomes the seat count of every migrated game. Boards are
+	/// packed, and ended games are queued for pruning in the order they ended.
+	///
+	/// The first release burned tickets on join and minted the reward of a winner, so the pots
+	/// of live games are held nowhere. They are issued once into the escrow, which pays them
+	/// out from now on: total issuance goes back to what it was before those tickets were
+	/// bought, plus the existential deposit of the escrow if it did not exist yet.
+	pub fn migrate<T: Config>() -> Weight {
+		let db_weight = T::DbWeight::get();
+		if StorageVersion::get::<Pallet<T>>() >= 1 {
+			return db_weight.reads(1)
+		}
+
+		let module = <Pallet<T>>::name().as_bytes();
+		let open: Vec<ID> = take_storage_value(module, b"GameOpen", &[]).unwrap_or_default();
+		let started: Vec<ID> = take_storage_value(module, b"GameStart", &[]).unwrap_or_default();
+		let seats: u8 = take_storage_value(module, b"MaxGomoku", &[]).unwrap_or(2);
+		let ended: Vec<ID> = take_storage_value(module, b"GetEndedGames", &[]).unwrap_or_default();
+		let now = <frame_system::Pallet<T>>::block_number();
+		let mut reads: Weight = 5;
+		let mut writes: Weight = 5;
+
+		// the first release kept every game it ever hosted, those in neither list are ended
+		<Games<T>>::translate::<OldGame<T::AccountId, BalanceOf<T>>, _>(|game_id, old| {
+			reads += 1;
+			writes += 1;
+			let status = if started.contains(&game_id) {
+				GameStatus::Start
+			} else if open.contains(&game_id) {
+				GameStatus::Open
+			} else {
+				GameStatus::End
+			};
+			Some(Game {
+				id: old.id,
+				host: old.host,
+				ticket: old.ticket,
+				expire: now.saturating_add(T::OpenGameTimeout::get()),
+				status,
+				settings: GameSettings { players: seats, ..Default::default() },
+			})
+		});
+
+		let mut pot = <BalanceOf<T>>::zero();
+		for (lobby, game_ids) in [(Lobby::Open, &open), (Lobby::Started, &started)] {
+			for game_id in game_ids.iter() {
+				let _ = <Pallet<T>>::list_game(lobby, *game_id);
+				if let Some(game) = <Games<T>>::get(game_id) {
+					let seated = <Players<T>>::decode_len(game_id).unwrap_or(0) as u32;
+					pot = pot.saturating_add(game.ticket.saturating_mul(seated.into()));
+				}
+				reads += 3;
+				writes += 3;
+			}
+		}
+
+		let escrow = <Pallet<T>>::account_id();
+		let min = T::Currency::minimum_balance();
+		if T::Currency::free_balance(&escrow) < min {
+			let _ = T::Currency::make_free_balance_be(&escrow, min);
+		}
+		// re-issues the tickets the first release burned, see above
+		let _ = T::Currency::deposit_creating(&escrow, pot);
+
+		<Turn<T>>::translate::<T::AccountId, _>(|_, player| {
+			reads += 1;
+			writes += 1;
+			Some(PlayerTurn { player, deadline: now.saturating_add(T::MoveTimeout::get()) })
+		});
+
+		<GomokuGame<T>>::translate::<OldBoard, _>(|_, cells| {
+			reads += 1;
+			writes += 1;
+			Some(packed(cells))
+		});
+
+		<EndedGames<T>>::translate::<OldEndedGame<T::AccountId, BalanceOf<T>>, _>(
+			|game_id, old| {
+				reads += 2;
+				writes += 2;
+				// seats still in `Players` move over. The first release dropped them when it
+				// finished a game, then the host had the first of two seats and the winner the
+				// other, unless the host won.
+				let mut players = <Players<T>>::take(game_id);
+				if players.is_empty() {
+					let _ = players.try_push(old.host.clone());
+					if old.winner != old.host {
+						let _ = players.try_push(old.winner.clone());
+					}
+				}
+				Some(EndedGame {
+					id: old.id,
+					host: old.host,
+					ticket: old.ticket,
+					game_map: packed(old.game_map),
+					outcome: GameOutcome::Win(old.winner),
+					players,
+					ended_at: now,
+				})
+			},
+		);
+		let mut tail = <EndedTail<T>>::get();
+		for game_id in ended.into_iter().filter(|game_id| <EndedGames<T>>::contains_key(game_id)) {
+			<EndedGameQueue<T>>::insert(tail, game_id);
+			tail = tail.saturating_add(1);
+			reads += 1;
+			writes += 1;
+		}
+		<EndedTail<T>>::put(tail);
+
+		// the first release took a fixed 1% of every pot
+		<HouseRake<T>>::put(Perbill::from_percent(1));
+		StorageVersion::new(1).put::<Pallet<T>>();
+
+		db_weight.reads_writes(reads, writes)
+	}
+}
@@ -1,13 +1,13 @@
 use crate as pallet_gomoku;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, weights::constants::RocksDbWeight, PalletId};
 use frame_system as system;
 
-use frame_support::traits::{Currency, OnFinalize, OnInitialize};
+use frame_support::traits::{Currency, GenesisBuild, OnFinalize, OnIdle, OnInitialize};
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
-	AccountId32,
+	AccountId32, Perbill,
 };
 
 pub use pallet_balances::Call as BalancesCall;
@@ -17,6 +17,7 @@ type Block = frame_system::mocking::MockBlock<Test>;
 
 pub const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
 pub const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+pub const CHARLIE: AccountId32 = AccountId32::new([3u8; 32]);
 
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
@@ -26,7 +27,7 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		PalletGame: pallet_gomoku::{Pallet, Call, Storage, Event<T>},
+		PalletGame: pallet_gomoku::{Pallet, Call, Storage, Config<T>, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
 		// Event: Event,
@@ -60,7 +61,7 @@ impl system::Config for Test {
 	type BaseCallFilter = frame_support::traits::Everything;
 	type BlockWeights = ();
 	type BlockLength = ();
-	type DbWeight = ();
+	type DbWeight = RocksDbWeight;
 	type Origin = Origin;
 	type Call = Call;
 	type Index = u64;
@@ -83,34 +84,49 @@ impl system::Config for Test {
 }
 
 parameter_types! {
-	pub const MaxGomokuPlayer: u32 = 2;
+	pub const MaxGomokuPlayer: u32 = 4;
 	pub const MaxGame: u32 = 10;
-	pub const MaxOpenGame: u32 = 10;
-	pub const MaxStartGame: u32 = 10;
 	pub const OpenGameFee: u32 = 1000000000u32;
-	pub const MaxEndedGame: u32 = 1000000000u32;
+	pub const EndedGameRetention: u64 = 50;
+	pub const MaxMoves: u32 = 361;
+	pub const GomokuPalletId: PalletId = PalletId(*b"py/gomok");
+	pub const MoveTimeout: u64 = 10;
+	pub const OpenGameTimeout: u64 = 100;
 }
 
 impl pallet_gomoku::Config for Test {
 	type Event = Event;
 	type Currency = Balances;
+	type PalletId = GomokuPalletId;
 	type MaxGomokuPlayer = MaxGomokuPlayer;
 	type MaxGame = MaxGame;
-	type MaxOpenGame = MaxOpenGame;
-	type MaxStartGame = MaxStartGame;
 	type OpenGameFee = OpenGameFee;
-	type MaxEndedGame = MaxEndedGame;
+	type EndedGameRetention = EndedGameRetention;
+	type MaxMoves = MaxMoves;
 	type GameRandomness = RandomnessCollectiveFlip;
+	type MoveTimeout = MoveTimeout;
+	type OpenGameTimeout = OpenGameTimeout;
+	type WeightInfo = ();
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_gomoku::GenesisConfig::<Test> {
+		open_fee: 0,
+		house_rake: Perbill::from_percent(1),
+		fee_recipient: None,
+		board_limits: Default::default(),
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	t.into()
 }
 
 pub fn run_to_block(n: u64) {
 	while System::block_number() < n {
 		if System::block_number() > 1 {
+			PalletGame::on_idle(System::block_number(), u64::MAX);
 			PalletGame::on_finalize(System::block_number());
 			System::on_finalize(System::block_number());
 		}
@@ -120,6 +136,17 @@ pub fn run_to_block(n: u64) {
 	}
 }
 
+/// Events deposited by the gomoku pallet so far, oldest first.
+pub fn gomoku_events() -> Vec<pallet_gomoku::Event<Test>> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::PalletGame(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
 pub struct ExtBuilder;
 
 // impl ExtBuilder {
@@ -139,11 +166,30 @@ impl ExtBuilder {
 		}
 		.assimilate_storage(&mut t)
 		.unwrap();
-
+		pallet_gomoku::GenesisConfig::<Test> {
+			open_fee: 0,
+			house_rake: Perbill::from_percent(1),
+			fee_recipient: None,
+			board_limits: Default::default(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
 
 		let mut ext = sp_io::TestExternalities::new(t);
 		ext.execute_with(|| System::set_block_number(1));
 		ext
 
 	}
+
+	pub fn build_and_execute(self, test: impl FnOnce() -> ()) {
+		execute_and_check(self.build(), test)
+	}
+}
+
+/// Run `test`, then check the storage invariants of the gomoku pallet.
+pub fn execute_and_check(mut ext: sp_io::TestExternalities, test: impl FnOnce() -> ()) {
+	ext.execute_with(|| {
+		test();
+		PalletGame::do_try_state().unwrap();
+	});
 }
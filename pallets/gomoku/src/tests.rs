@@ -1,9 +1,22 @@
-use crate::{mock::*, Config, EndedGame, Error};
-use frame_support::{assert_err, assert_ok, traits::Currency};
+use crate::{
+	board::Board,
+	mock::*,
+	rules::{self, Forbidden, RuleSet},
+	BoardLimits, Color, Config, EndedGame, Error, GameOutcome, GameSettings, GameStatus, Lobby,
+	Move, OpeningPhase, OpeningRule, ReplayOutcome,
+};
+use frame_support::{
+	assert_err, assert_noop, assert_ok,
+	migration::put_storage_value,
+	traits::{Currency, Get, PalletInfoAccess, StorageVersion},
+	BoundedVec, StorageHasher, Twox64Concat,
+};
+use proptest::prelude::*;
+use sp_runtime::{AccountId32, DispatchError, Perbill};
 
 #[test]
 fn host_game_should_available() {
-	new_test_ext().execute_with(|| {
+	execute_and_check(new_test_ext(), || {
 		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
 		assert_ok!(PalletGame::is_host_available(&ALICE));
 	});
@@ -11,106 +24,121 @@ fn host_game_should_available() {
 
 #[test]
 fn open_new_game_should_works() {
-	new_test_ext().execute_with(|| {
+	execute_and_check(new_test_ext(), || {
 		run_to_block(10);
 		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
 		assert_ok!(PalletGame::is_host_available(&ALICE));
-		assert_ok!(PalletGame::open_game(ALICE, 10u64));
+		assert_ok!(PalletGame::open_game(ALICE, 10u64, GameSettings::default()));
 	});
 }
 
 #[test]
 fn test_winner_should_works() {
-	new_test_ext().execute_with(|| {
+	execute_and_check(new_test_ext(), || {
+		let settings = GameSettings::default();
 		{
-			let mut game_map = [[-1i8; 15]; 15];
+			let mut game_map = Board::new(15, 15);
 			let player_index: i8 = 1;
 			let x: usize = 7;
 			let y: usize = 7;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[6][7] = player_index;
-			game_map[8][7] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(6, 7, player_index);
+			game_map.set(8, 7, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[5][7] = player_index;
-			game_map[9][7] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(5, 7, player_index);
+			game_map.set(9, 7, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 
-			game_map[4][7] = player_index;
-			game_map[10][7] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(4, 7, player_index);
+			game_map.set(10, 7, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 		}
 
 		{
-			let mut game_map = [[-1i8; 15]; 15];
+			let mut game_map = Board::new(15, 15);
 			let player_index: i8 = 1;
 			let x: usize = 7;
 			let y: usize = 7;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[7][6] = player_index;
-			game_map[7][8] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(7, 6, player_index);
+			game_map.set(7, 8, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[7][5] = player_index;
-			game_map[7][9] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(7, 5, player_index);
+			game_map.set(7, 9, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 
-			game_map[7][4] = player_index;
-			game_map[7][10] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(7, 4, player_index);
+			game_map.set(7, 10, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 		}
 
 		{
-			let mut game_map = [[-1i8; 15]; 15];
+			let mut game_map = Board::new(15, 15);
 			let player_index: i8 = 1;
 			let x: usize = 7;
 			let y: usize = 7;
 
-			game_map[8][8] = player_index;
-			game_map[6][6] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(8, 8, player_index);
+			game_map.set(6, 6, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[9][9] = player_index;
-			game_map[5][5] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(9, 9, player_index);
+			game_map.set(5, 5, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 
-			game_map[10][10] = player_index;
-			game_map[4][4] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(10, 10, player_index);
+			game_map.set(4, 4, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 		}
 
 		{
-			let mut game_map = [[-1i8; 15]; 15];
+			let mut game_map = Board::new(15, 15);
 			let player_index: i8 = 1;
 			let x: usize = 7;
 			let y: usize = 7;
 
-			game_map[6][8] = player_index;
-			game_map[8][6] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(6, 8, player_index);
+			game_map.set(8, 6, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, false, "should not win");
 
-			game_map[5][9] = player_index;
-			game_map[9][5] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(5, 9, player_index);
+			game_map.set(9, 5, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 
-			game_map[4][10] = player_index;
-			game_map[10][4] = player_index;
-			let is_winner = PalletGame::check_winner(game_map, player_index, x, y).unwrap();
+			game_map.set(4, 10, player_index);
+			game_map.set(10, 4, player_index);
+			let is_winner =
+				settings.rule.is_win(&game_map, settings.win_length, player_index, x, y);
 			assert_eq!(is_winner, true, "should win");
 		}
 	});
@@ -118,7 +146,7 @@ fn test_winner_should_works() {
 
 #[test]
 fn game_flow_should_works() {
-	new_test_ext().execute_with(|| {
+	execute_and_check(new_test_ext(), || {
 		run_to_block(10);
 		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
 		let _ = <Test as Config>::Currency::deposit_creating(&BOB, 100_000_000);
@@ -127,27 +155,30 @@ fn game_flow_should_works() {
 		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
 		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
 
-		assert_ok!(PalletGame::set_max_player(2u8));
-
-		// Max game player
-		let max_gomoku_player = PalletGame::max_gomoku_player();
-		assert_eq!(max_gomoku_player, 2, "Gomoku max player not correct");
 		// OPEN AND JOIN GAME
-		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket));
-		let game_open_ids = PalletGame::game_open();
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_open_ids = PalletGame::open_games(0);
 		assert_eq!(game_open_ids.len(), 1, "game opened length not correct");
 		let game_id = game_open_ids.first().unwrap();
 		// check storage after open game
 		{
-			let game_open = PalletGame::game_open();
-			assert_eq!(game_open.contains(game_id), true, "game_id should not on the GameOpen");
+			assert_eq!(
+				PalletGame::is_listed(Lobby::Open, game_id),
+				true,
+				"game_id should on the open lobby"
+			);
 
 			let game_hosting = PalletGame::game_hosting(ALICE).unwrap();
 			assert_eq!(game_hosting, *game_id, "Game hosting should exsit");
 		}
 
 		// JOIN GAME
-		assert_ok!(PalletGame::join(Origin::signed(BOB), *game_id));
+		assert_ok!(PalletGame::join(Origin::signed(BOB), *game_id, None));
 
 		let alice_after_balance = <Test as Config>::Currency::free_balance(ALICE);
 		let bob_after_balance = <Test as Config>::Currency::free_balance(BOB);
@@ -161,10 +192,16 @@ fn game_flow_should_works() {
 
 		// check storage after start game
 		{
-			let game_open = PalletGame::game_open();
-			assert_eq!(game_open.contains(game_id), false, "game_id should not on the GameOpen");
-			let game_start = PalletGame::game_start();
-			assert_eq!(game_start.contains(game_id), true, "game_id should on the GameStart");
+			assert_eq!(
+				PalletGame::is_listed(Lobby::Open, game_id),
+				false,
+				"game_id should not on the open lobby"
+			);
+			assert_eq!(
+				PalletGame::is_listed(Lobby::Started, game_id),
+				true,
+				"game_id should on the started lobby"
+			);
 		}
 
 		// PLAY GAME
@@ -191,9 +228,12 @@ fn game_flow_should_works() {
 				assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 3));
 				let bob_after_balance = <Test as Config>::Currency::free_balance(BOB);
 
-				let mut reward = ticket * 2;
-				reward = (reward as f64 - (reward as f64 * 0.01)) as u64;
-				assert_eq!(bob_after_balance - bob_before_balance, reward, "reward receipt not correct");
+				let reward = ticket * 2 - Perbill::from_percent(1) * (ticket * 2);
+				assert_eq!(
+					bob_after_balance - bob_before_balance,
+					reward,
+					"reward receipt not correct"
+				);
 			}
 
 			run_to_block(20);
@@ -209,11 +249,2338 @@ fn game_flow_should_works() {
 			// check storage
 			{
 				let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
-				assert_eq!(ended_game.winner, BOB, "winner not correct");
+				assert_eq!(ended_game.outcome, GameOutcome::Win(BOB), "outcome not correct");
+
+				assert_eq!(
+					PalletGame::ended_game_at(0),
+					Some(*game_id),
+					"ended_games must contain game_id"
+				);
+			}
+		}
+	});
+}
+
+#[test]
+fn player_left_open_game_should_refund() {
+	execute_and_check(new_test_ext(), || {
+		run_to_block(10);
+		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
+		let _ = <Test as Config>::Currency::deposit_creating(&BOB, 100_000_000);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+		let bob_after_balance = <Test as Config>::Currency::free_balance(BOB);
+
+		assert_eq!(bob_before_balance, bob_after_balance, "Bob ticket should be refunded");
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should not be playing");
+		assert_eq!(PalletGame::players(game_id).into_inner(), vec![ALICE], "players not correct");
+		assert_eq!(PalletGame::is_listed(Lobby::Open, &game_id), true, "game should still be open");
+
+		// Bob can join another lobby afterwards
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_err!(PalletGame::left(Origin::signed(CHARLIE)), <Error<Test>>::PlayerNotPlaying);
+	});
+}
+
+#[test]
+fn host_left_open_game_should_tear_down() {
+	execute_and_check(new_test_ext(), || {
+		run_to_block(10);
+		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
+		let _ = <Test as Config>::Currency::deposit_creating(&BOB, 100_000_000);
+		let ticket = 10_000;
+
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+
+		assert_eq!(alice_before_balance, <Test as Config>::Currency::free_balance(ALICE));
+		assert_eq!(bob_before_balance, <Test as Config>::Currency::free_balance(BOB));
+		assert_eq!(PalletGame::games(game_id), None, "game should be removed");
+		assert_eq!(PalletGame::is_listed(Lobby::Open, &game_id), false, "game should not be open");
+		assert_eq!(PalletGame::game_hosting(ALICE), None, "Alice should not host");
+		assert_eq!(PalletGame::game_playing(ALICE), None, "Alice should not be playing");
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should not be playing");
+		assert_eq!(PalletGame::players(game_id).len(), 0, "players should be removed");
+	});
+}
+
+#[test]
+fn player_left_started_game_should_forfeit() {
+	execute_and_check(new_test_ext(), || {
+		run_to_block(10);
+		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
+		let _ = <Test as Config>::Currency::deposit_creating(&BOB, 100_000_000);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 7));
+
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+		assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+		let bob_after_balance = <Test as Config>::Currency::free_balance(BOB);
+
+		let reward = ticket * 2 - Perbill::from_percent(1) * (ticket * 2);
+		assert_eq!(bob_after_balance - bob_before_balance, reward, "reward receipt not correct");
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Forfeit(BOB), "outcome not correct");
+		assert_eq!(ended_game.game_map.get(7, 7), Some(0), "game map not correct");
+		assert_eq!(PalletGame::is_listed(Lobby::Started, &game_id), false, "game should be ended");
+		assert_eq!(PalletGame::game_playing(ALICE), None, "Alice should not be playing");
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should not be playing");
+	});
+}
+
+#[test]
+fn escrow_should_keep_total_issuance() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let open_fee = 1_000;
+		let ticket = 10_000;
+		crate::OpenGameFee::<Test>::put(open_fee);
+
+		let escrow = PalletGame::account_id();
+		let escrow_before_balance = <Test as Config>::Currency::free_balance(&escrow);
+		let issuance = <Test as Config>::Currency::total_issuance();
+
+		// open, join then cancel
+		{
+			assert_ok!(PalletGame::open_and_join(
+				Origin::signed(ALICE),
+				ticket,
+				GameSettings::default(),
+				None
+			));
+			let game_id = PalletGame::open_games(0)[0];
+			assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+			assert_eq!(
+				<Test as Config>::Currency::free_balance(&escrow),
+				escrow_before_balance + open_fee + ticket * 2,
+				"escrow should hold the tickets"
+			);
+			assert_eq!(<Test as Config>::Currency::total_issuance(), issuance);
+
+			assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+			assert_eq!(
+				<Test as Config>::Currency::free_balance(&escrow),
+				escrow_before_balance + open_fee,
+				"escrow should refund the tickets"
+			);
+			assert_eq!(<Test as Config>::Currency::total_issuance(), issuance);
+		}
+
+		run_to_block(11);
+
+		// open, join then finish
+		{
+			assert_ok!(PalletGame::open_and_join(
+				Origin::signed(ALICE),
+				ticket,
+				GameSettings::default(),
+				None
+			));
+			let game_id = PalletGame::open_games(0)[0];
+			assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+			assert_eq!(<Test as Config>::Currency::total_issuance(), issuance);
+
+			assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+			for y in 3..7 {
+				assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, y));
+				assert_ok!(PalletGame::play(Origin::signed(BOB), 1, y));
+			}
+			let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 7));
+			let alice_after_balance = <Test as Config>::Currency::free_balance(ALICE);
+			let reward = alice_after_balance - alice_before_balance;
+
+			assert_eq!(
+				<Test as Config>::Currency::free_balance(&escrow),
+				escrow_before_balance + open_fee * 2 + ticket * 2 - reward,
+				"escrow should pay the winner"
+			);
+			assert_eq!(<Test as Config>::Currency::total_issuance(), issuance);
+		}
+	});
+}
+
+#[test]
+fn rake_should_go_to_fee_recipient() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_noop!(
+			PalletGame::set_house_rake(Origin::signed(ALICE), Perbill::from_percent(10)),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			PalletGame::set_fee_recipient(Origin::signed(ALICE), Some(ALICE)),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(PalletGame::set_house_rake(Origin::root(), Perbill::from_percent(10)));
+		assert_ok!(PalletGame::set_fee_recipient(Origin::root(), Some(CHARLIE)));
+		assert_eq!(PalletGame::house_rake(), Perbill::from_percent(10));
+		assert_eq!(PalletGame::fee_recipient(), Some(CHARLIE));
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let issuance = <Test as Config>::Currency::total_issuance();
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+
+		assert_eq!(
+			<Test as Config>::Currency::free_balance(ALICE) - alice_before_balance,
+			18_000,
+			"reward receipt not correct"
+		);
+		assert_eq!(<Test as Config>::Currency::free_balance(CHARLIE), 2_000, "rake not correct");
+		assert_eq!(<Test as Config>::Currency::total_issuance(), issuance);
+	});
+}
+
+#[test]
+fn uncollected_rake_should_not_be_reported() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		assert_ok!(PalletGame::set_house_rake(Origin::root(), Perbill::from_percent(10)));
+		assert_ok!(PalletGame::set_fee_recipient(Origin::root(), Some(CHARLIE)));
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+
+		// the escrow keeps the reward only, moving the rake would reap it
+		let escrow = PalletGame::account_id();
+		let min = <Test as Config>::Currency::minimum_balance();
+		<Test as Config>::Currency::make_free_balance_be(&escrow, min + 18_000);
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+
+		assert_eq!(<Test as Config>::Currency::free_balance(CHARLIE), 0, "rake not collected");
+		assert_eq!(
+			gomoku_events().last(),
+			Some(&crate::Event::GameEnded(
+				game_id,
+				GameOutcome::Forfeit(ALICE),
+				Some(ALICE),
+				18_000,
+				0,
+				PalletGame::prunable_at(System::block_number())
+			))
+		);
+	});
+}
+
+#[test]
+fn split_pot_should_round_rake_down() {
+	execute_and_check(new_test_ext(), || {
+		assert_eq!(PalletGame::split_pot(20_000), (19_800, 200));
+		assert_eq!(PalletGame::split_pot(199), (198, 1));
+		assert_eq!(PalletGame::split_pot(99), (99, 0));
+		assert_eq!(PalletGame::split_pot(u64::MAX), (u64::MAX - u64::MAX / 100, u64::MAX / 100));
+
+		assert_ok!(PalletGame::set_house_rake(Origin::root(), Perbill::from_percent(0)));
+		assert_eq!(PalletGame::split_pot(20_000), (20_000, 0));
+	});
+}
+
+#[test]
+fn claim_timeout_should_works() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 7));
+
+		let turn = PalletGame::turn(game_id).unwrap();
+		assert_eq!(turn.player, BOB, "turn not correct");
+		assert_eq!(turn.deadline, 10 + MoveTimeout::get(), "deadline not correct");
+
+		assert_noop!(
+			PalletGame::claim_timeout(Origin::signed(ALICE)),
+			<Error<Test>>::TurnNotExpired
+		);
+		assert_noop!(
+			PalletGame::claim_timeout(Origin::signed(BOB)),
+			<Error<Test>>::CanNotClaimOwnTurn
+		);
+
+		// skip the hooks so nothing settles the game on its own
+		System::set_block_number(turn.deadline + 1);
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 8, 8), <Error<Test>>::TurnExpired);
+
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		assert_ok!(PalletGame::claim_timeout(Origin::signed(ALICE)));
+		assert_eq!(
+			<Test as Config>::Currency::free_balance(ALICE) - alice_before_balance,
+			ticket * 2 - Perbill::from_percent(1) * (ticket * 2),
+			"reward receipt not correct"
+		);
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Timeout(ALICE), "outcome not correct");
+		assert_eq!(PalletGame::turn(game_id), None, "turn should be removed");
+	});
+}
+
+#[test]
+fn expired_turn_should_be_settled_on_idle() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+
+		run_to_block(10 + MoveTimeout::get());
+		assert_eq!(PalletGame::is_listed(Lobby::Started, &game_id), true, "game should be playing");
+
+		run_to_block(10 + MoveTimeout::get() + 2);
+		assert_eq!(PalletGame::is_listed(Lobby::Started, &game_id), false, "game should be ended");
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Timeout(BOB), "outcome not correct");
+		assert_eq!(PalletGame::game_playing(ALICE), None, "Alice should not be playing");
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should not be playing");
+	});
+}
+
+#[test]
+fn expired_open_game_should_refund_players() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_eq!(PalletGame::games(game_id).unwrap().expire, 10 + OpenGameTimeout::get());
+
+		run_to_block(10 + OpenGameTimeout::get() + 2);
+		assert_eq!(PalletGame::games(game_id), None, "game should be removed");
+		assert_eq!(PalletGame::is_listed(Lobby::Open, &game_id), false, "game should not be open");
+		assert_eq!(PalletGame::game_playing(ALICE), None, "Alice should not be playing");
+		assert_eq!(PalletGame::game_hosting(ALICE), None, "Alice should not host");
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance);
+	});
+}
+
+#[test]
+fn full_board_should_end_in_draw() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		// a full board without five in a row, only the last cell is left for Bob
+		let mut game_map = Board::new(15, 15);
+		for x in 0..15 {
+			for y in 0..15 {
+				game_map.set(x, y, ((x / 2 + y) % 2) as i8);
+			}
+		}
+		game_map.set(14, 14, -1);
+		assert_eq!(game_map.is_full(), false, "board should not be full");
+		crate::GomokuGame::<Test>::insert(game_id, game_map);
+
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 14, 14));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Draw, "outcome not correct");
+		assert_eq!(ended_game.game_map.is_full(), true, "board should be full");
+
+		let refund = ticket - Perbill::from_percent(1) * ticket;
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance + refund);
+		assert_eq!(<Test as Config>::Currency::free_balance(BOB), bob_before_balance + refund);
+	});
+}
+
+#[test]
+fn draw_offer_should_works() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_noop!(PalletGame::offer_draw(Origin::signed(ALICE)), <Error<Test>>::GameNotStarted);
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+
+		// a move declines the pending offer
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 7));
+		assert_ok!(PalletGame::offer_draw(Origin::signed(ALICE)));
+		assert_eq!(PalletGame::draw_offer(game_id), Some(ALICE));
+		assert_noop!(
+			PalletGame::accept_draw(Origin::signed(ALICE)),
+			<Error<Test>>::CanNotAcceptOwnDraw
+		);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 8, 8));
+		assert_eq!(PalletGame::draw_offer(game_id), None);
+		assert_noop!(PalletGame::accept_draw(Origin::signed(BOB)), <Error<Test>>::DrawNotOffered);
+
+		assert_ok!(PalletGame::offer_draw(Origin::signed(BOB)));
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+		assert_ok!(PalletGame::accept_draw(Origin::signed(ALICE)));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Draw, "outcome not correct");
+		assert_eq!(PalletGame::draw_offer(game_id), None, "draw offer should be removed");
+
+		let refund = ticket - Perbill::from_percent(1) * ticket;
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance + refund);
+		assert_eq!(<Test as Config>::Currency::free_balance(BOB), bob_before_balance + refund);
+	});
+}
+
+#[test]
+fn game_status_transition_should_works() {
+	assert_eq!(GameStatus::Open.can_transition_to(&GameStatus::Start), true);
+	assert_eq!(GameStatus::Open.can_transition_to(&GameStatus::Cancel), true);
+	assert_eq!(GameStatus::Start.can_transition_to(&GameStatus::End), true);
+
+	assert_eq!(GameStatus::Open.can_transition_to(&GameStatus::End), false);
+	assert_eq!(GameStatus::Start.can_transition_to(&GameStatus::Cancel), false);
+	assert_eq!(GameStatus::Start.can_transition_to(&GameStatus::Open), false);
+	assert_eq!(GameStatus::End.can_transition_to(&GameStatus::Start), false);
+	assert_eq!(GameStatus::Cancel.can_transition_to(&GameStatus::Open), false);
+}
+
+#[test]
+fn game_status_should_follow_lifecycle() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Open);
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+		assert_noop!(
+			PalletGame::join(Origin::signed(CHARLIE), game_id, None),
+			<Error<Test>>::GameNotOpen
+		);
+		assert_noop!(
+			PalletGame::set_game_status(&game_id, GameStatus::Cancel),
+			<Error<Test>>::InvalidStatusTransition
+		);
+		assert_noop!(PalletGame::cancel(Origin::signed(ALICE)), <Error<Test>>::GameNotOpen);
+
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::End);
+		assert_noop!(
+			PalletGame::set_game_status(&game_id, GameStatus::Start),
+			<Error<Test>>::InvalidStatusTransition
+		);
+	});
+}
+
+#[test]
+fn host_cancel_game_should_refund_players() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let bob_before_balance = <Test as Config>::Currency::free_balance(BOB);
+
+		// the host does not need to play in the game
+		assert_ok!(PalletGame::open(Origin::signed(CHARLIE), ticket, GameSettings::default()));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(ALICE), game_id, None));
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+
+		assert_noop!(PalletGame::cancel(Origin::signed(ALICE)), <Error<Test>>::NotGameHost);
+		assert_ok!(PalletGame::cancel(Origin::signed(CHARLIE)));
+
+		assert_eq!(PalletGame::games(game_id), None, "game should be removed");
+		assert_eq!(PalletGame::is_listed(Lobby::Open, &game_id), false, "game should not be open");
+		assert_eq!(PalletGame::game_hosting(CHARLIE), None, "Charlie should not host");
+		assert_eq!(PalletGame::game_playing(ALICE), None, "Alice should not be playing");
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should not be playing");
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance);
+		assert_eq!(<Test as Config>::Currency::free_balance(BOB), bob_before_balance);
+		assert_noop!(PalletGame::start(Origin::signed(ALICE)), <Error<Test>>::PlayerNotPlaying);
+	});
+}
+
+#[test]
+fn move_history_should_replay_to_stored_result() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		for y in 3..7 {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 7, y));
+			run_to_block(System::block_number() + 1);
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 1, y));
+		}
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+
+		let moves = PalletGame::game_moves(game_id);
+		assert_eq!(moves.len(), 9, "moves length not correct");
+		assert_eq!(moves[0], Move { player: 1, x: 7, y: 3, block: 10 }, "first move not correct");
+		assert_eq!(moves[1], Move { player: 0, x: 1, y: 3, block: 11 }, "second move not correct");
+		assert_eq!(moves[8], Move { player: 1, x: 7, y: 7, block: 14 }, "last move not correct");
 
-				let ended_games = PalletGame::get_ended_games();
-				assert_eq!(ended_games.contains(game_id), true, "ended_games must contain game_id");
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Win(BOB), "outcome not correct");
+		assert_eq!(ended_game.players.clone().into_inner(), vec![ALICE, BOB]);
+		assert_eq!(ended_game.game_map.get(7, 7), Some(1), "winning move should be on the map");
+
+		let settings = PalletGame::games(game_id).unwrap().settings;
+		let outcome = PalletGame::replay(&settings, &moves).unwrap();
+		assert_eq!(outcome, ReplayOutcome::Win(1), "replay outcome not correct");
+		if let ReplayOutcome::Win(index) = outcome {
+			assert_eq!(Some(&ended_game.players[index as usize]), ended_game.outcome.winner());
+		}
+	});
+}
+
+#[test]
+fn replay_should_reject_invalid_moves() {
+	execute_and_check(new_test_ext(), || {
+		let settings = GameSettings::default();
+		let m = |player, x, y| Move { player, x, y, block: 1u64 };
+
+		assert_eq!(PalletGame::replay(&settings, &[]), Ok(ReplayOutcome::Unfinished));
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(0, 7, 7), m(1, 8, 8)]),
+			Ok(ReplayOutcome::Unfinished)
+		);
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(0, 15, 7)]),
+			Err(<Error<Test>>::PlaceNotCorrect)
+		);
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(0, 7, 7), m(1, 7, 7)]),
+			Err(<Error<Test>>::PlaceNotEmpty)
+		);
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(200, 7, 7)]),
+			Err(<Error<Test>>::InvalidPlayerIndex)
+		);
+
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(0, 7, 7), m(2, 8, 8)]),
+			Err(<Error<Test>>::InvalidPlayerIndex)
+		);
+
+		// a seat can not play twice in a row
+		let forged: Vec<_> = (3..8).map(|y| m(0, 7, y)).collect();
+		assert_eq!(PalletGame::replay(&settings, &forged), Err(<Error<Test>>::NotYourTurn));
+
+		let mut moves = Vec::new();
+		for y in 3..7 {
+			moves.push(m(0, 7, y));
+			moves.push(m(1, 1, y));
+		}
+		moves.push(m(0, 7, 7));
+		assert_eq!(PalletGame::replay(&settings, &moves), Ok(ReplayOutcome::Win(0)));
+		moves.push(m(1, 1, 1));
+		assert_eq!(PalletGame::replay(&settings, &moves), Err(<Error<Test>>::GameAlreadyEnded));
+
+		// a seat passed over is out and never moves again
+		let settings = GameSettings { players: 3, ..Default::default() };
+		let rotation = [m(1, 7, 7), m(2, 8, 8), m(0, 9, 9), m(2, 6, 6)];
+		assert_eq!(PalletGame::replay(&settings, &rotation), Ok(ReplayOutcome::Unfinished));
+		let mut returned = rotation.to_vec();
+		returned.push(m(1, 5, 5));
+		assert_eq!(PalletGame::replay(&settings, &returned), Err(<Error<Test>>::NotYourTurn));
+	});
+}
+
+#[test]
+fn small_board_should_win_with_four() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { width: 9, height: 9, win_length: 4, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_eq!(PalletGame::games(game_id).unwrap().settings, settings);
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		let board = PalletGame::gomoku_game(game_id).unwrap();
+		assert_eq!((board.width, board.height), (9, 9), "board size not correct");
+
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 9, 0), Error::<Test>::PlaceNotCorrect);
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 0, 9), Error::<Test>::PlaceNotCorrect);
+
+		for x in 0..3 {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), x, x));
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), x, 8));
+		}
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 3, 3));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Win(BOB), "outcome not correct");
+		assert_eq!(
+			PalletGame::replay(&settings, &PalletGame::game_moves(game_id)),
+			Ok(ReplayOutcome::Win(1))
+		);
+	});
+}
+
+#[test]
+fn small_board_should_end_in_draw_when_full() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { width: 9, height: 9, win_length: 4, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		// no four in a row anywhere, only (8, 7) is left for Bob
+		let mut game_map = Board::new(9, 9);
+		for x in 0..9 {
+			for y in 0..9 {
+				game_map.set(x, y, ((x / 2 + y) % 2) as i8);
+			}
+		}
+		game_map.set(8, 7, -1);
+		crate::GomokuGame::<Test>::insert(game_id, game_map);
+
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 8, 7));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Draw, "outcome not correct");
+		assert_eq!(ended_game.game_map.is_full(), true, "board should be full");
+	});
+}
+
+#[test]
+fn open_should_respect_board_limits() {
+	ExtBuilder.build_and_execute(|| {
+		let ticket = 10_000;
+		let open = |width, height, win_length| {
+			PalletGame::open(
+				Origin::signed(ALICE),
+				ticket,
+				GameSettings { width, height, win_length, ..Default::default() },
+			)
+		};
+
+		assert_noop!(open(8, 15, 5), Error::<Test>::BoardSizeNotAllowed);
+		assert_noop!(open(15, 20, 5), Error::<Test>::BoardSizeNotAllowed);
+		assert_noop!(open(15, 15, 3), Error::<Test>::WinLengthNotAllowed);
+		assert_noop!(open(15, 15, 6), Error::<Test>::WinLengthNotAllowed);
+
+		let limits =
+			BoardLimits { min_size: 7, max_size: 15, min_win_length: 3, max_win_length: 6 };
+		assert_noop!(
+			PalletGame::set_board_limits(Origin::signed(ALICE), limits),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			PalletGame::set_board_limits(
+				Origin::root(),
+				BoardLimits { min_size: 7, max_size: 20, ..limits }
+			),
+			Error::<Test>::InvalidBoardLimits
+		);
+		assert_noop!(
+			PalletGame::set_board_limits(
+				Origin::root(),
+				BoardLimits { max_win_length: 8, ..limits }
+			),
+			Error::<Test>::InvalidBoardLimits
+		);
+		assert_ok!(PalletGame::set_board_limits(Origin::root(), limits));
+		assert_eq!(PalletGame::board_limits(), limits);
+
+		assert_noop!(open(15, 19, 5), Error::<Test>::BoardSizeNotAllowed);
+		assert_ok!(open(7, 15, 3));
+	});
+}
+
+#[test]
+fn standard_rule_should_not_win_with_overline() {
+	execute_and_check(new_test_ext(), || {
+		let freestyle = GameSettings::default();
+		let standard = GameSettings { rule: RuleSet::Standard, ..Default::default() };
+		let player_index = 0;
+
+		// six in a row with the new stone in the middle, one line per direction
+		for &(dx, dy) in [(1i32, 0i32), (0, 1), (1, 1), (1, -1)].iter() {
+			let mut game_map = Board::new(15, 15);
+			let at = |i: i32| ((7 + i * dx) as usize, (7 + i * dy) as usize);
+			for i in [-3, -2, -1, 1].iter() {
+				let (x, y) = at(*i);
+				game_map.set(x, y, player_index);
 			}
+
+			// exactly five
+			let is_winner =
+				standard.rule.is_win(&game_map, standard.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "five should win standard in ({}, {})", dx, dy);
+
+			let (x, y) = at(2);
+			game_map.set(x, y, player_index);
+			let is_winner =
+				standard.rule.is_win(&game_map, standard.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, false, "six should not win standard in ({}, {})", dx, dy);
+			let is_winner =
+				freestyle.rule.is_win(&game_map, freestyle.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "six should win freestyle in ({}, {})", dx, dy);
+
+			let (x, y) = at(-4);
+			game_map.set(x, y, player_index);
+			let is_winner =
+				standard.rule.is_win(&game_map, standard.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, false, "seven should not win standard in ({}, {})", dx, dy);
+			let is_winner =
+				freestyle.rule.is_win(&game_map, freestyle.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "seven should win freestyle in ({}, {})", dx, dy);
+		}
+	});
+}
+
+#[test]
+fn standard_rule_should_win_with_five_next_to_overline() {
+	execute_and_check(new_test_ext(), || {
+		let standard = GameSettings { rule: RuleSet::Standard, ..Default::default() };
+		let player_index = 1;
+
+		// an overline across and exactly five down through (7, 7)
+		let mut game_map = Board::new(15, 15);
+		for x in [2, 3, 4, 5, 6, 8].iter() {
+			game_map.set(*x, 7, player_index);
 		}
+		assert_eq!(standard.rule.is_win(&game_map, standard.win_length, player_index, 7, 7), false);
+		for y in [3, 4, 5, 6].iter() {
+			game_map.set(7, *y, player_index);
+		}
+		assert_eq!(standard.rule.is_win(&game_map, standard.win_length, player_index, 7, 7), true);
 	});
 }
+
+#[test]
+fn standard_game_should_continue_after_overline() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { rule: RuleSet::Standard, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		for (i, &y) in [3, 4, 5, 7, 8].iter().enumerate() {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 7, y));
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 1, i as u32 * 2));
+		}
+		// fills the gap and makes six in a row
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 6));
+
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+		assert!(PalletGame::ended_game(game_id).is_none(), "game should not be ended");
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "turn should pass on");
+	});
+}
+
+#[test]
+fn caro_rule_should_not_win_when_blocked_at_both_ends() {
+	execute_and_check(new_test_ext(), || {
+		let caro = GameSettings { rule: RuleSet::Caro, ..Default::default() };
+		let freestyle = GameSettings::default();
+		let (player_index, opponent) = (0, 1);
+
+		for &(dx, dy) in [(1i32, 0i32), (0, 1), (1, 1), (1, -1)].iter() {
+			let at = |i: i32| ((7 + i * dx) as usize, (7 + i * dy) as usize);
+			let mut game_map = Board::new(15, 15);
+			for i in [-2, -1, 1, 2].iter() {
+				let (x, y) = at(*i);
+				game_map.set(x, y, player_index);
+			}
+
+			// open five
+			let is_winner = caro.rule.is_win(&game_map, caro.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "open five should win in ({}, {})", dx, dy);
+
+			// half-open five
+			let (x, y) = at(-3);
+			game_map.set(x, y, opponent);
+			let is_winner = caro.rule.is_win(&game_map, caro.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "half-open five should win in ({}, {})", dx, dy);
+
+			// blocked at both ends
+			let (x, y) = at(3);
+			game_map.set(x, y, opponent);
+			let is_winner = caro.rule.is_win(&game_map, caro.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, false, "blocked five should not win in ({}, {})", dx, dy);
+			let is_winner =
+				freestyle.rule.is_win(&game_map, freestyle.win_length, player_index, 7, 7);
+			assert_eq!(is_winner, true, "blocked five should win freestyle in ({}, {})", dx, dy);
+		}
+	});
+}
+
+#[test]
+fn caro_rule_should_not_count_edge_as_block() {
+	execute_and_check(new_test_ext(), || {
+		let caro = GameSettings { rule: RuleSet::Caro, ..Default::default() };
+		let (player_index, opponent) = (1, 0);
+
+		// five along the top edge, blocked only on the inside
+		let mut game_map = Board::new(15, 15);
+		for y in 1..5 {
+			game_map.set(0, y, player_index);
+		}
+		game_map.set(0, 5, opponent);
+		assert_eq!(caro.rule.is_win(&game_map, caro.win_length, player_index, 0, 0), true);
+
+		// five in the corner diagonal, blocked only on the inside
+		let mut game_map = Board::new(15, 15);
+		for i in 0..4 {
+			game_map.set(14 - i, 14 - i, player_index);
+		}
+		game_map.set(9, 9, opponent);
+		assert_eq!(caro.rule.is_win(&game_map, caro.win_length, player_index, 10, 10), true);
+
+		// five on the anti-diagonal touching the left and bottom edges, blocked inside
+		let mut game_map = Board::new(15, 15);
+		for i in 1..5 {
+			game_map.set(14 - i, i, player_index);
+		}
+		game_map.set(9, 5, opponent);
+		assert_eq!(caro.rule.is_win(&game_map, caro.win_length, player_index, 14, 0), true);
+
+		// five next to the edge with an opponent stone on both ends
+		let mut game_map = Board::new(15, 15);
+		for y in 2..6 {
+			game_map.set(14, y, player_index);
+		}
+		game_map.set(14, 1, opponent);
+		game_map.set(14, 7, opponent);
+		assert_eq!(caro.rule.is_win(&game_map, caro.win_length, player_index, 14, 6), false);
+	});
+}
+
+#[test]
+fn caro_game_should_continue_after_blocked_five() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { rule: RuleSet::Caro, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		// Alice blocks both ends of Bob's column first
+		let alice_moves = [(7, 2), (7, 8), (0, 0), (0, 2)];
+		for (&y, &(x, alice_y)) in [3, 4, 5, 6].iter().zip(alice_moves.iter()) {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 7, y));
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), x, alice_y));
+		}
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+		assert!(PalletGame::ended_game(game_id).is_none(), "game should not be ended");
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "turn should pass on");
+	});
+}
+
+/// Board from rows of `X` (black, index 0), `O` (white, index 1) and `.`, with the move
+/// to look at marked `*`.
+fn position(rows: &[&str]) -> (Board, usize, usize) {
+	let mut board = Board::new(rows.len() as u8, rows[0].len() as u8);
+	let mut mv = (0, 0);
+	for (x, row) in rows.iter().enumerate() {
+		for (y, cell) in row.chars().enumerate() {
+			match cell {
+				'X' => board.set(x, y, 0),
+				'O' => board.set(x, y, 1),
+				'*' => {
+					mv = (x, y);
+					true
+				},
+				_ => true,
+			};
+		}
+	}
+	(board, mv.0, mv.1)
+}
+
+#[test]
+fn renju_should_detect_forbidden_positions() {
+	let corpus: [(&str, [&str; 9], Option<Forbidden>); 12] = [
+		(
+			"open three and open three",
+			[
+				".........",
+				".........",
+				"....X....",
+				"....X....",
+				"..XX*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::DoubleThree),
+		),
+		(
+			"split three and open three",
+			[
+				".........",
+				".........",
+				"....X....",
+				"....X....",
+				".X.X*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::DoubleThree),
+		),
+		(
+			"three on both diagonals",
+			[
+				".........",
+				".........",
+				"..X...X..",
+				"...X.X...",
+				"....*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::DoubleThree),
+		),
+		(
+			"blocked three is not a three",
+			[
+				".........",
+				".........",
+				"....X....",
+				"....X....",
+				".OXX*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			None,
+		),
+		(
+			"three against the edge is not a three",
+			[
+				".........",
+				".........",
+				"..X......",
+				"..X......",
+				"XX*......",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			None,
+		),
+		(
+			"four and four",
+			[
+				"....O....",
+				"....X....",
+				"....X....",
+				"....X....",
+				"OXXX*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::DoubleFour),
+		),
+		(
+			"two fours on one line",
+			[
+				".........",
+				".........",
+				".........",
+				".........",
+				".X.X*X.X.",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::DoubleFour),
+		),
+		(
+			"four and three",
+			[
+				".........",
+				".........",
+				"....X....",
+				"....X....",
+				"OXXX*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			None,
+		),
+		(
+			"six in a row",
+			[
+				".........",
+				".........",
+				".........",
+				".........",
+				".XXX*XX..",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::Overline),
+		),
+		(
+			"overline down a column",
+			[
+				"....X....",
+				"....X....",
+				"....X....",
+				"....X....",
+				"....*....",
+				"....X....",
+				".........",
+				".........",
+				".........",
+			],
+			Some(Forbidden::Overline),
+		),
+		(
+			"five beats double-four",
+			[
+				".........",
+				"....X....",
+				"....X....",
+				"....X....",
+				".XXX*X...",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			None,
+		),
+		(
+			"single open three",
+			[
+				".........",
+				".........",
+				".........",
+				".........",
+				"..XX*....",
+				".........",
+				".........",
+				".........",
+				".........",
+			],
+			None,
+		),
+	];
+
+	for (name, rows, expected) in corpus.iter() {
+		let (board, x, y) = position(rows);
+		assert_eq!(RuleSet::Renju.forbidden(&board, 0, 0, x, y), *expected, "{}", name);
+		assert_eq!(rules::renju_forbidden(&board, 0, x, y), *expected, "{}", name);
+		// white may play anything
+		assert_eq!(RuleSet::Renju.forbidden(&board, 1, 0, x, y), None, "{}", name);
+		// and so may black under the other rule sets
+		assert_eq!(RuleSet::Freestyle.forbidden(&board, 0, 0, x, y), None, "{}", name);
+	}
+}
+
+#[test]
+fn renju_should_reject_forbidden_black_move() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { rule: RuleSet::Renju, ..Default::default() };
+
+		assert_noop!(
+			PalletGame::open(
+				Origin::signed(ALICE),
+				ticket,
+				GameSettings { win_length: 4, ..settings }
+			),
+			Error::<Test>::WinLengthNotAllowed
+		);
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		// Bob moves first and plays black, Alice answers far away
+		let black_moves = [(7, 5), (7, 6), (5, 7), (6, 7)];
+		for (i, &(x, y)) in black_moves.iter().enumerate() {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), x, y));
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 0, i as u32 * 2));
+		}
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 7, 7), Error::<Test>::ForbiddenMove);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 12, 12));
+
+		// white has no forbidden moves
+		let white_moves = [(1, 1), (2, 1), (3, 3), (3, 2)];
+		for (i, &(x, y)) in white_moves.iter().enumerate() {
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), x, y));
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 14, i as u32 * 2));
+		}
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 3, 1));
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+
+		let moves = PalletGame::game_moves(game_id);
+		assert_eq!(PalletGame::replay(&settings, &moves), Ok(ReplayOutcome::Unfinished));
+		let mut forbidden = moves.into_inner();
+		forbidden.truncate(8);
+		forbidden.push(Move { player: 1, x: 7, y: 7, block: 10 });
+		assert_eq!(PalletGame::replay(&settings, &forbidden), Err(Error::<Test>::ForbiddenMove));
+	});
+}
+
+#[test]
+fn pro_opening_should_restrict_first_and_third_stone() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { opening: OpeningRule::Pro, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		assert_noop!(
+			PalletGame::play(Origin::signed(BOB), 6, 6),
+			Error::<Test>::OpeningMoveNotAllowed
+		);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 8, 8));
+		assert_noop!(
+			PalletGame::play(Origin::signed(BOB), 9, 5),
+			Error::<Test>::OpeningMoveNotAllowed
+		);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 10, 7));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 8, 7));
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 8));
+
+		let moves = PalletGame::game_moves(game_id);
+		assert_eq!(PalletGame::replay(&settings, &moves), Ok(ReplayOutcome::Unfinished));
+		let m = |player, x, y| Move { player, x, y, block: 10u64 };
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(1, 6, 7)]),
+			Err(Error::<Test>::OpeningMoveNotAllowed)
+		);
+		assert_eq!(
+			PalletGame::replay(&settings, &[m(1, 7, 7), m(0, 8, 8), m(1, 5, 5)]),
+			Err(Error::<Test>::OpeningMoveNotAllowed)
+		);
+	});
+}
+
+#[test]
+fn swap_opening_should_let_second_player_take_black() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { opening: OpeningRule::Swap, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert_eq!(PalletGame::opening(game_id), Some(OpeningPhase::Tentative));
+
+		// Bob places black, white and black
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+		assert_noop!(PalletGame::play(Origin::signed(ALICE), 8, 8), Error::<Test>::NotYourTurn);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 8, 8));
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 6, 8));
+		assert_eq!(PalletGame::opening(game_id), Some(OpeningPhase::FirstChoice));
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "Alice should choose");
+
+		let board = PalletGame::gomoku_game(game_id).unwrap();
+		assert_eq!(
+			(board.get(7, 7), board.get(8, 8), board.get(6, 8)),
+			(Some(1), Some(0), Some(1))
+		);
+
+		assert_noop!(PalletGame::play(Origin::signed(ALICE), 5, 5), Error::<Test>::ColorNotChosen);
+		assert_noop!(
+			PalletGame::choose_color(Origin::signed(BOB), Color::Black),
+			Error::<Test>::NotYourTurn
+		);
+		assert_ok!(PalletGame::choose_color(Origin::signed(ALICE), Color::Black));
+		assert_eq!(
+			gomoku_events().last(),
+			Some(&crate::Event::ColorChosen(game_id, ALICE, Color::Black))
+		);
+
+		// Alice took the seat of the black stones, Bob plays white and moves next
+		assert_eq!(PalletGame::players(game_id).into_inner(), vec![BOB, ALICE]);
+		assert_eq!(PalletGame::opening(game_id), None);
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB, "white should move");
+		assert_noop!(
+			PalletGame::choose_color(Origin::signed(BOB), Color::Black),
+			Error::<Test>::NotChoosingColor
+		);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 5, 5));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 5, 6));
+
+		let board = PalletGame::gomoku_game(game_id).unwrap();
+		assert_eq!((board.get(5, 5), board.get(5, 6)), (Some(0), Some(1)));
+		let moves = PalletGame::game_moves(game_id);
+		assert_eq!(PalletGame::replay(&settings, &moves), Ok(ReplayOutcome::Unfinished));
+	});
+}
+
+#[test]
+fn swap_opening_should_let_second_player_take_white() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { opening: OpeningRule::Swap, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		for &(x, y) in [(7, 7), (8, 8), (6, 8)].iter() {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), x, y));
+		}
+		assert_ok!(PalletGame::choose_color(Origin::signed(ALICE), Color::White));
+
+		assert_eq!(PalletGame::players(game_id).into_inner(), vec![ALICE, BOB]);
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "white should move");
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 5, 5));
+		assert_eq!(PalletGame::gomoku_game(game_id).unwrap().get(5, 5), Some(0));
+	});
+}
+
+#[test]
+fn swap2_opening_should_allow_extra_stones() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { opening: OpeningRule::Swap2, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		for &(x, y) in [(7, 7), (8, 8), (6, 8)].iter() {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), x, y));
+		}
+
+		// Alice places a white and a black stone instead of picking
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 6, 6));
+		assert_eq!(PalletGame::opening(game_id), Some(OpeningPhase::ExtraStones));
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "Alice should go on");
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 9, 9));
+		assert_eq!(PalletGame::opening(game_id), Some(OpeningPhase::SecondChoice));
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB, "Bob should choose");
+
+		let board = PalletGame::gomoku_game(game_id).unwrap();
+		assert_eq!((board.get(6, 6), board.get(9, 9)), (Some(0), Some(1)));
+
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 5, 5), Error::<Test>::ColorNotChosen);
+		assert_ok!(PalletGame::choose_color(Origin::signed(BOB), Color::White));
+
+		// Bob gave the black stones to Alice and moves next as white
+		assert_eq!(PalletGame::players(game_id).into_inner(), vec![BOB, ALICE]);
+		assert_eq!(PalletGame::opening(game_id), None);
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB, "white should move");
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 5, 5));
+		assert_eq!(PalletGame::gomoku_game(game_id).unwrap().get(5, 5), Some(0));
+	});
+}
+
+#[test]
+fn choose_color_should_need_swap_opening() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert_eq!(PalletGame::opening(game_id), None);
+		assert_noop!(
+			PalletGame::choose_color(Origin::signed(BOB), Color::Black),
+			Error::<Test>::NotChoosingColor
+		);
+	});
+}
+
+#[test]
+fn commit_reveal_should_pick_first_player() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { commit_reveal: true, ..Default::default() };
+		let (alice_secret, bob_secret) = ([1u8; 32], [2u8; 32]);
+		let alice_commitment = PalletGame::commitment_of(&ALICE, &alice_secret);
+		let bob_commitment = PalletGame::commitment_of(&BOB, &bob_secret);
+
+		assert_noop!(
+			PalletGame::open_and_join(Origin::signed(ALICE), ticket, settings, None),
+			Error::<Test>::CommitmentMissing
+		);
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			settings,
+			Some(alice_commitment)
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_noop!(
+			PalletGame::join(Origin::signed(BOB), game_id, None),
+			Error::<Test>::CommitmentMissing
+		);
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, Some(bob_commitment)));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert!(
+			!gomoku_events().iter().any(|e| matches!(e, crate::Event::GameStarted(..))),
+			"game should start after the reveals"
+		);
+
+		// nobody may move before both secrets are out
+		assert!(PalletGame::turn(game_id).is_none(), "turn should wait for the reveals");
+		assert_noop!(PalletGame::play(Origin::signed(BOB), 7, 7), Error::<Test>::RevealPending);
+		assert_noop!(
+			PalletGame::reveal(Origin::signed(ALICE), bob_secret),
+			Error::<Test>::InvalidReveal
+		);
+		assert_ok!(PalletGame::reveal(Origin::signed(ALICE), alice_secret));
+		assert_noop!(
+			PalletGame::reveal(Origin::signed(ALICE), alice_secret),
+			Error::<Test>::AlreadyRevealed
+		);
+		assert!(PalletGame::turn(game_id).is_none(), "turn should wait for Bob");
+		assert_ok!(PalletGame::reveal(Origin::signed(BOB), bob_secret));
+
+		// (1 ^ 2) % 2 picks the second seat
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB, "Bob should move first");
+		assert_eq!(
+			gomoku_events().last(),
+			Some(&crate::Event::GameStarted(game_id, vec![BOB, ALICE].try_into().unwrap()))
+		);
+		assert_eq!(PalletGame::reveal_deadline(game_id), None);
+		assert_eq!(PalletGame::commitment(game_id, ALICE), None);
+		assert_eq!(PalletGame::revealed(game_id, BOB), None);
+		assert_noop!(
+			PalletGame::reveal(Origin::signed(BOB), bob_secret),
+			Error::<Test>::NotRevealing
+		);
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+	});
+}
+
+#[test]
+fn commitment_should_only_be_taken_by_commit_reveal_games() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let commitment = PalletGame::commitment_of(&BOB, &[0u8; 32]);
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_noop!(
+			PalletGame::join(Origin::signed(BOB), game_id, Some(commitment)),
+			Error::<Test>::CommitmentNotExpected
+		);
+	});
+}
+
+#[test]
+fn missing_reveal_should_forfeit_after_deadline() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { commit_reveal: true, ..Default::default() };
+		let alice_secret = [7u8; 32];
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			settings,
+			Some(PalletGame::commitment_of(&ALICE, &alice_secret))
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(
+			Origin::signed(BOB),
+			game_id,
+			Some(PalletGame::commitment_of(&BOB, &[8u8; 32]))
+		));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		let deadline = PalletGame::reveal_deadline(game_id).unwrap();
+		assert_eq!(deadline, 10 + <Test as Config>::MoveTimeout::get());
+
+		assert_ok!(PalletGame::reveal(Origin::signed(ALICE), alice_secret));
+		assert_noop!(
+			PalletGame::claim_timeout(Origin::signed(ALICE)),
+			Error::<Test>::RevealNotExpired
+		);
+
+		run_to_block(deadline + 1);
+		assert_noop!(
+			PalletGame::reveal(Origin::signed(BOB), [8u8; 32]),
+			Error::<Test>::RevealExpired
+		);
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		assert_ok!(PalletGame::claim_timeout(Origin::signed(ALICE)));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Timeout(ALICE), "outcome not correct");
+		let reward = ticket * 2 - Perbill::from_percent(1) * (ticket * 2);
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance + reward);
+		assert_eq!(PalletGame::reveal_deadline(game_id), None);
+		assert_eq!(PalletGame::revealed(game_id, ALICE), None);
+	});
+}
+
+#[test]
+fn no_reveal_should_end_in_draw_on_idle() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let settings = GameSettings { commit_reveal: true, ..Default::default() };
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			settings,
+			Some(PalletGame::commitment_of(&ALICE, &[1u8; 32]))
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(
+			Origin::signed(BOB),
+			game_id,
+			Some(PalletGame::commitment_of(&BOB, &[2u8; 32]))
+		));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+
+		let deadline = PalletGame::reveal_deadline(game_id).unwrap();
+		run_to_block(deadline + 2);
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Draw, "outcome not correct");
+		assert_eq!(PalletGame::game_playing(ALICE), None);
+		assert_eq!(PalletGame::commitment(game_id, BOB), None);
+	});
+}
+
+fn open_three_player_game(ticket: u64) -> [u8; 32] {
+	let _ = <Test as Config>::Currency::deposit_creating(&CHARLIE, 1_000_000_000);
+	assert_ok!(PalletGame::open_and_join(
+		Origin::signed(ALICE),
+		ticket,
+		GameSettings { players: 3, ..Default::default() },
+		None
+	));
+	let game_id = PalletGame::open_games(0)[0];
+	assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+	assert_noop!(PalletGame::start(Origin::signed(BOB)), Error::<Test>::NotEnoughPlayer);
+	assert_ok!(PalletGame::join(Origin::signed(CHARLIE), game_id, None));
+	assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+	game_id
+}
+
+#[test]
+fn eliminated_host_should_not_leave_twice() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let game_id = open_three_player_game(10_000);
+
+		assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+		assert_eq!(PalletGame::eliminated(game_id).into_inner(), vec![ALICE]);
+		assert_eq!(PalletGame::game_hosting(ALICE), Some(game_id), "Alice should still host");
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB);
+
+		assert_noop!(PalletGame::left(Origin::signed(ALICE)), Error::<Test>::PlayerNotPlaying);
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+	});
+}
+
+#[test]
+fn three_player_game_should_rotate_turns() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let game_id = open_three_player_game(ticket);
+
+		for y in 0..4 {
+			assert_noop!(
+				PalletGame::play(Origin::signed(BOB), 4, y * 2),
+				Error::<Test>::NotYourTurn
+			);
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 0, y * 2));
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 4, y * 2));
+			assert_ok!(PalletGame::play(Origin::signed(CHARLIE), 9, y));
+		}
+		let board = PalletGame::gomoku_game(game_id).unwrap();
+		assert_eq!(
+			(board.get(0, 0), board.get(4, 0), board.get(9, 0)),
+			(Some(0), Some(1), Some(2))
+		);
+
+		let charlie_before_balance = <Test as Config>::Currency::free_balance(CHARLIE);
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 0, 8));
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 4, 8));
+		assert_ok!(PalletGame::play(Origin::signed(CHARLIE), 9, 4));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Win(CHARLIE), "outcome not correct");
+		assert_eq!(ended_game.players.clone().into_inner(), vec![ALICE, BOB, CHARLIE]);
+		let reward = ticket * 3 - Perbill::from_percent(1) * (ticket * 3);
+		assert_eq!(
+			<Test as Config>::Currency::free_balance(CHARLIE),
+			charlie_before_balance + reward
+		);
+		let moves = PalletGame::game_moves(game_id);
+		let settings = PalletGame::games(game_id).unwrap().settings;
+		assert_eq!(PalletGame::replay(&settings, &moves), Ok(ReplayOutcome::Win(2)));
+	});
+}
+
+#[test]
+fn three_player_game_should_go_on_without_leaver() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let game_id = open_three_player_game(ticket);
+
+		assert_noop!(
+			PalletGame::offer_draw(Origin::signed(ALICE)),
+			Error::<Test>::DrawNeedsTwoPlayers
+		);
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 7));
+
+		// Bob leaves on his turn, his stone stays and the turn skips him
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 8, 8));
+		assert_ok!(PalletGame::play(Origin::signed(CHARLIE), 9, 9));
+		assert_ok!(PalletGame::play(Origin::signed(ALICE), 7, 8));
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+		assert_eq!(PalletGame::eliminated(game_id).into_inner(), vec![BOB]);
+		assert_eq!(PalletGame::game_playing(BOB), None, "Bob should be free to join");
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, CHARLIE, "turn should skip Bob");
+		assert_eq!(PalletGame::games(game_id).unwrap().status, GameStatus::Start);
+		assert_eq!(PalletGame::gomoku_game(game_id).unwrap().get(8, 8), Some(1));
+
+		assert_ok!(PalletGame::play(Origin::signed(CHARLIE), 9, 8));
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, ALICE, "turn should skip Bob");
+
+		// two players left may agree on a draw and share the pot
+		let alice_before_balance = <Test as Config>::Currency::free_balance(ALICE);
+		let charlie_before_balance = <Test as Config>::Currency::free_balance(CHARLIE);
+		assert_ok!(PalletGame::offer_draw(Origin::signed(ALICE)));
+		assert_ok!(PalletGame::accept_draw(Origin::signed(CHARLIE)));
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Draw, "outcome not correct");
+		let share = (ticket * 3 - Perbill::from_percent(1) * (ticket * 3)) / 2;
+		assert_eq!(<Test as Config>::Currency::free_balance(ALICE), alice_before_balance + share);
+		assert_eq!(
+			<Test as Config>::Currency::free_balance(CHARLIE),
+			charlie_before_balance + share
+		);
+		assert_eq!(PalletGame::eliminated(game_id).len(), 0);
+	});
+}
+
+#[test]
+fn three_player_timeout_should_take_out_late_player() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let game_id = open_three_player_game(ticket);
+
+		// Alice never moves, Charlie takes her out and Bob moves next
+		run_to_block(10 + <Test as Config>::MoveTimeout::get() + 1);
+		assert_ok!(PalletGame::claim_timeout(Origin::signed(CHARLIE)));
+		assert_eq!(PalletGame::eliminated(game_id).into_inner(), vec![ALICE]);
+		assert_eq!(gomoku_events().last(), Some(&crate::Event::PlayerTimedOut(game_id, ALICE)));
+		assert_eq!(PalletGame::turn(game_id).unwrap().player, BOB, "Bob should move next");
+
+		// Bob does not move either and is settled on idle, Charlie is the last one left
+		let charlie_before_balance = <Test as Config>::Currency::free_balance(CHARLIE);
+		let deadline = PalletGame::turn(game_id).unwrap().deadline;
+		run_to_block(deadline + 2);
+
+		let ended_game: EndedGame<Test> = PalletGame::ended_game(game_id).unwrap();
+		assert_eq!(ended_game.outcome, GameOutcome::Timeout(CHARLIE), "outcome not correct");
+		let reward = ticket * 3 - Perbill::from_percent(1) * (ticket * 3);
+		assert_eq!(
+			<Test as Config>::Currency::free_balance(CHARLIE),
+			charlie_before_balance + reward
+		);
+	});
+}
+
+#[test]
+fn two_player_rules_should_need_two_players() {
+	ExtBuilder.build_and_execute(|| {
+		let ticket = 10_000;
+
+		for settings in [
+			GameSettings { rule: RuleSet::Renju, players: 3, ..Default::default() },
+			GameSettings { opening: OpeningRule::Pro, players: 3, ..Default::default() },
+			GameSettings { opening: OpeningRule::Swap2, players: 3, ..Default::default() },
+		]
+		.iter()
+		{
+			assert_noop!(
+				PalletGame::open(Origin::signed(ALICE), ticket, *settings),
+				Error::<Test>::RuleNeedsTwoPlayers
+			);
+		}
+		assert_ok!(PalletGame::open(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings {
+				rule: RuleSet::Caro,
+				commit_reveal: true,
+				players: 3,
+				..Default::default()
+			}
+		));
+		// only this game is limited to three players
+		assert_ok!(PalletGame::open(
+			Origin::signed(BOB),
+			ticket,
+			GameSettings { rule: RuleSet::Renju, ..Default::default() }
+		));
+	});
+}
+
+#[test]
+fn players_should_be_within_bounds() {
+	ExtBuilder.build_and_execute(|| {
+		let ticket = 10_000;
+		let max_players = <Test as Config>::MaxGomokuPlayer::get() as u8;
+
+		for players in [0, 1, max_players + 1] {
+			assert_noop!(
+				PalletGame::open(
+					Origin::signed(ALICE),
+					ticket,
+					GameSettings { players, ..Default::default() }
+				),
+				Error::<Test>::PlayersNotAllowed
+			);
+		}
+		assert_ok!(PalletGame::open(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings { players: max_players, ..Default::default() }
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_eq!(PalletGame::games(game_id).unwrap().settings.players, max_players);
+	});
+}
+
+#[test]
+fn game_events_should_follow_game_flow() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert_eq!(
+			gomoku_events(),
+			vec![
+				crate::Event::NewGameOpen(game_id, ALICE, ticket),
+				crate::Event::PlayerJoinGame(ALICE, game_id),
+				crate::Event::PlayerJoinGame(BOB, game_id),
+				crate::Event::GameStarted(game_id, vec![BOB, ALICE].try_into().unwrap()),
+			]
+		);
+
+		for y in 3..7 {
+			assert_ok!(PalletGame::play(Origin::signed(BOB), 7, y));
+			assert_eq!(
+				gomoku_events().last(),
+				Some(&crate::Event::MovePlayed(game_id, BOB, 7, y as u8))
+			);
+			assert_ok!(PalletGame::play(Origin::signed(ALICE), 1, y));
+			assert_eq!(
+				gomoku_events().last(),
+				Some(&crate::Event::MovePlayed(game_id, ALICE, 1, y as u8))
+			);
+		}
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+
+		let rake = Perbill::from_percent(1) * (ticket * 2);
+		let events = gomoku_events();
+		assert_eq!(
+			events[events.len() - 2..],
+			[
+				crate::Event::MovePlayed(game_id, BOB, 7, 7),
+				crate::Event::GameEnded(
+					game_id,
+					GameOutcome::Win(BOB),
+					Some(BOB),
+					ticket * 2 - rake,
+					rake,
+					PalletGame::prunable_at(System::block_number())
+				),
+			]
+		);
+	});
+}
+
+#[test]
+fn leave_and_cancel_events_should_be_deposited() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+		let events = gomoku_events();
+		assert_eq!(
+			events[events.len() - 2..],
+			[crate::Event::PlayerLeft(game_id, BOB), crate::Event::Refunded(game_id, BOB, ticket),]
+		);
+
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::cancel(Origin::signed(ALICE)));
+		let events = gomoku_events();
+		assert_eq!(
+			events[events.len() - 3..],
+			[
+				crate::Event::Refunded(game_id, ALICE, ticket),
+				crate::Event::Refunded(game_id, BOB, ticket),
+				crate::Event::GameCancelled(game_id),
+			]
+		);
+	});
+}
+
+#[test]
+fn forfeit_and_draw_events_should_be_deposited() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let rake = Perbill::from_percent(1) * (ticket * 2);
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert_ok!(PalletGame::left(Origin::signed(BOB)));
+		let events = gomoku_events();
+		assert_eq!(
+			events[events.len() - 2..],
+			[
+				crate::Event::PlayerLeft(game_id, BOB),
+				crate::Event::GameEnded(
+					game_id,
+					GameOutcome::Forfeit(ALICE),
+					Some(ALICE),
+					ticket * 2 - rake,
+					rake,
+					10 + EndedGameRetention::get() + 1
+				),
+			]
+		);
+
+		run_to_block(11);
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(BOB),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(ALICE), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(ALICE)));
+		assert_ok!(PalletGame::offer_draw(Origin::signed(ALICE)));
+		assert_ok!(PalletGame::accept_draw(Origin::signed(BOB)));
+		assert_eq!(
+			gomoku_events().last(),
+			Some(&crate::Event::GameEnded(
+				game_id,
+				GameOutcome::Draw,
+				None,
+				ticket * 2 - rake,
+				rake,
+				PalletGame::prunable_at(System::block_number())
+			))
+		);
+	});
+}
+
+#[test]
+fn queries_should_describe_games() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		let open_games = PalletGame::open_game_infos(0);
+		assert_eq!(open_games.len(), 1);
+		assert_eq!(open_games[0].host, ALICE);
+		assert_eq!(open_games[0].status, GameStatus::Open);
+		assert_eq!(open_games[0].players, vec![ALICE]);
+		assert_eq!(PalletGame::game_of(&ALICE), Some(game_id));
+		assert_eq!(PalletGame::game_of(&BOB), None);
+		assert_eq!(PalletGame::board_of(&game_id), None);
+
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert!(PalletGame::open_game_infos(0).is_empty());
+		assert_eq!(PalletGame::game_info(&game_id).unwrap().status, GameStatus::Start);
+		let deadline = PalletGame::turn(game_id).unwrap().deadline;
+		assert_eq!(PalletGame::current_turn(&game_id), Some((BOB, deadline)));
+
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+		assert_eq!(PalletGame::board_of(&game_id).unwrap().get(7, 7), Some(1));
+		assert_eq!(PalletGame::current_turn(&game_id).unwrap().0, ALICE);
+
+		assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+		assert_eq!(PalletGame::game_of(&BOB), None);
+		assert_eq!(PalletGame::current_turn(&game_id), None);
+		let ended = PalletGame::ended_game_infos(0);
+		assert_eq!(ended.len(), 1);
+		assert_eq!(ended[0].id, game_id);
+		assert_eq!(ended[0].outcome, GameOutcome::Forfeit(BOB));
+		assert_eq!(ended[0].board.get(7, 7), Some(1));
+		assert!(PalletGame::ended_game_infos(1).is_empty());
+	});
+}
+
+#[test]
+fn lobby_should_page_games_opened_in_one_block() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let hosts: Vec<AccountId32> = (0..25u8).map(|i| AccountId32::new([100 + i; 32])).collect();
+		for host in hosts.iter() {
+			let _ = <Test as Config>::Currency::deposit_creating(host, 10_000_000_000);
+			assert_ok!(PalletGame::open(
+				Origin::signed(host.clone()),
+				ticket,
+				GameSettings::default()
+			));
+		}
+
+		assert_eq!(PalletGame::lobby_size(Lobby::Open), 25);
+		let first_page = PalletGame::open_games(0);
+		let second_page = PalletGame::open_games(1);
+		assert_eq!(first_page.len(), 20);
+		assert_eq!(second_page.len(), 5);
+		assert!(PalletGame::open_games(2).is_empty());
+		// games opened in the same block still get their own id
+		let mut ids = [first_page.clone(), second_page.clone()].concat();
+		ids.sort();
+		ids.dedup();
+		assert_eq!(ids.len(), 25);
+
+		// the last game takes over the slot of a cancelled one
+		let cancelled = first_page[3];
+		let host = PalletGame::games(cancelled).unwrap().host;
+		assert_ok!(PalletGame::cancel(Origin::signed(host)));
+		assert_eq!(PalletGame::is_listed(Lobby::Open, &cancelled), false);
+		assert_eq!(PalletGame::lobby_game(Lobby::Open, 3), Some(second_page[4]));
+		assert_eq!(PalletGame::lobby_size(Lobby::Open), 24);
+		assert_eq!(PalletGame::open_games(1).len(), 4);
+
+		run_to_block(10 + OpenGameTimeout::get() + 2);
+		assert_eq!(PalletGame::lobby_size(Lobby::Open), 0);
+		for host in hosts.iter() {
+			assert_eq!(PalletGame::game_hosting(host), None, "every game should have expired");
+		}
+	});
+}
+
+#[test]
+fn ended_game_should_be_pruned_after_retention() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			ticket,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+		assert_ok!(PalletGame::start(Origin::signed(BOB)));
+		assert_ok!(PalletGame::play(Origin::signed(BOB), 7, 7));
+		assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+		assert_eq!(PalletGame::ended_game(game_id).unwrap().ended_at, 10);
+		assert_eq!(PalletGame::ended_game_infos(0).len(), 1);
+
+		run_to_block(10 + EndedGameRetention::get());
+		assert!(PalletGame::ended_game(game_id).is_some(), "game should be kept");
+
+		run_to_block(10 + EndedGameRetention::get() + 2);
+		assert_eq!(PalletGame::ended_game(game_id), None);
+		assert_eq!(PalletGame::games(game_id), None);
+		assert!(PalletGame::game_moves(game_id).is_empty());
+		assert_eq!(PalletGame::ended_game_at(0), None);
+		assert_eq!((PalletGame::ended_head(), PalletGame::ended_tail()), (1, 1));
+		assert!(PalletGame::ended_game_infos(0).is_empty());
+		assert_eq!(gomoku_events().last(), Some(&crate::Event::EndedGamePruned(game_id)));
+	});
+}
+
+#[test]
+fn migration_should_carry_over_first_release_storage() {
+	execute_and_check(new_test_ext(), || {
+		let module = <PalletGame as PalletInfoAccess>::name().as_bytes();
+		let ticket = 10_000u64;
+		let (open_id, started_id, ended_id, seated_id) =
+			([1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]);
+		put_storage_value(module, b"GameOpen", &[], vec![open_id]);
+		put_storage_value(module, b"GameStart", &[], vec![started_id]);
+		put_storage_value(module, b"GetEndedGames", &[], vec![ended_id, seated_id]);
+		put_storage_value(module, b"MaxGomoku", &[], 2u8);
+		for (game_id, host) in
+			[(open_id, ALICE), (started_id, BOB), (ended_id, CHARLIE), (seated_id, BOB)]
+		{
+			put_storage_value(
+				module,
+				b"Games",
+				&Twox64Concat::hash(&game_id),
+				(game_id, host, ticket),
+			);
+		}
+		crate::GameHosting::<Test>::insert(ALICE, open_id);
+		crate::GameHosting::<Test>::insert(BOB, started_id);
+		crate::Players::<Test>::insert(open_id, BoundedVec::try_from(vec![ALICE]).unwrap());
+		crate::Players::<Test>::insert(
+			started_id,
+			BoundedVec::try_from(vec![BOB, CHARLIE]).unwrap(),
+		);
+		for (player, game_id) in [(ALICE, open_id), (BOB, started_id), (CHARLIE, started_id)] {
+			crate::GamePlaying::<Test>::insert(player, game_id);
+		}
+		let mut cells = [[-1i8; 15]; 15];
+		cells[3][4] = 1;
+		for game_id in [started_id, ended_id, seated_id] {
+			put_storage_value(module, b"GomokuGame", &Twox64Concat::hash(&game_id), cells);
+		}
+		for (game_id, host, winner) in [(ended_id, CHARLIE, ALICE), (seated_id, BOB, BOB)] {
+			put_storage_value(
+				module,
+				b"EndedGames",
+				&Twox64Concat::hash(&game_id),
+				(game_id, host, ticket, cells, winner),
+			);
+		}
+		// left behind by a finished game, the first release removed these
+		crate::Players::<Test>::insert(seated_id, BoundedVec::try_from(vec![BOB, ALICE]).unwrap());
+		put_storage_value(module, b"Turn", &Twox64Concat::hash(&started_id), CHARLIE);
+		StorageVersion::new(0).put::<PalletGame>();
+		let escrow = PalletGame::account_id();
+		let escrow_balance = Balances::free_balance(&escrow);
+		let issuance = Balances::total_issuance();
+
+		crate::migrations::v1::migrate::<Test>();
+
+		assert_eq!(StorageVersion::get::<PalletGame>(), 1);
+		assert_eq!(PalletGame::open_games(0), vec![open_id]);
+		assert_eq!(PalletGame::started_games(0), vec![started_id]);
+		let game = PalletGame::games(open_id).unwrap();
+		assert_eq!((game.host, game.ticket, game.status), (ALICE, ticket, GameStatus::Open));
+		assert_eq!(game.expire, System::block_number() + OpenGameTimeout::get());
+		assert_eq!(game.settings, GameSettings::default());
+		assert_eq!(PalletGame::games(started_id).unwrap().status, GameStatus::Start);
+		let turn = PalletGame::turn(started_id).unwrap();
+		assert_eq!(
+			(turn.player, turn.deadline),
+			(CHARLIE, System::block_number() + MoveTimeout::get())
+		);
+		assert_eq!(Balances::free_balance(&escrow), escrow_balance + 3 * ticket);
+		// the three burned tickets come back, along with the deposit creating the escrow
+		assert_eq!(Balances::total_issuance(), issuance + 3 * ticket + ExistentialDeposit::get());
+		assert_eq!(PalletGame::house_rake(), Perbill::from_percent(1));
+
+		let mut board = Board::new(15, 15);
+		board.set(3, 4, 1);
+		assert_eq!(PalletGame::gomoku_game(started_id), Some(board.clone()));
+		assert_eq!(PalletGame::games(ended_id).unwrap().status, GameStatus::End);
+		let ended = PalletGame::ended_game(ended_id).unwrap();
+		assert_eq!((ended.game_map, ended.outcome), (board.clone(), GameOutcome::Win(ALICE)));
+		assert_eq!(ended.players.into_inner(), vec![CHARLIE, ALICE]);
+		assert_eq!(ended.ended_at, System::block_number());
+		let seated = PalletGame::ended_game(seated_id).unwrap();
+		assert_eq!((seated.game_map, seated.outcome), (board, GameOutcome::Win(BOB)));
+		assert_eq!(seated.players.into_inner(), vec![BOB, ALICE]);
+		assert!(PalletGame::players(seated_id).is_empty());
+		assert_eq!(PalletGame::ended_game_at(0), Some(ended_id));
+		assert_eq!(PalletGame::ended_game_at(1), Some(seated_id));
+		assert_eq!((PalletGame::ended_head(), PalletGame::ended_tail()), (0, 2));
+
+		let escrow_balance = Balances::free_balance(&escrow);
+		let issuance = Balances::total_issuance();
+		crate::migrations::v1::migrate::<Test>();
+		assert_eq!(Balances::free_balance(&escrow), escrow_balance, "migration should run once");
+		assert_eq!(Balances::total_issuance(), issuance);
+	});
+}
+
+#[test]
+fn limited_weight_should_spread_pruning_over_blocks() {
+	ExtBuilder.build_and_execute(|| {
+		run_to_block(10);
+		let ticket = 10_000;
+		let mut game_ids = vec![];
+		for _ in 0..3 {
+			assert_ok!(PalletGame::open_and_join(
+				Origin::signed(ALICE),
+				ticket,
+				GameSettings::default(),
+				None
+			));
+			let game_id = PalletGame::open_games(0)[0];
+			assert_ok!(PalletGame::join(Origin::signed(BOB), game_id, None));
+			assert_ok!(PalletGame::start(Origin::signed(BOB)));
+			assert_ok!(PalletGame::left(Origin::signed(ALICE)));
+			game_ids.push(game_id);
+		}
+		let prunable_at = 10 + EndedGameRetention::get() + 1;
+		assert!(matches!(
+			gomoku_events().last(),
+			Some(crate::Event::GameEnded(.., at)) if *at == prunable_at
+		));
+
+		let db_weight = <Test as frame_system::Config>::DbWeight::get();
+		let one_prune = db_weight.reads_writes(2, 1) + db_weight.reads_writes(2, 6);
+		PalletGame::prune_ended_games(prunable_at, one_prune - 1);
+		assert_eq!(PalletGame::ended_head(), 0, "nothing should fit");
+		for (pruned, block) in (prunable_at..prunable_at + 3).enumerate() {
+			System::set_block_number(block);
+			assert_eq!(PalletGame::prune_ended_games(block, one_prune), one_prune);
+			assert_eq!(PalletGame::ended_head(), pruned as u64 + 1);
+			assert_eq!(PalletGame::ended_game(game_ids[pruned]), None);
+			assert_eq!(
+				gomoku_events().last(),
+				Some(&crate::Event::EndedGamePruned(game_ids[pruned]))
+			);
+			for game_id in &game_ids[pruned + 1..] {
+				assert!(PalletGame::ended_game(game_id).is_some(), "game should be kept");
+			}
+		}
+	});
+}
+
+#[test]
+fn try_state_should_catch_corrupted_storage() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PalletGame::do_try_state());
+		crate::GamePlaying::<Test>::insert(ALICE, [9u8; 32]);
+		assert_err!(PalletGame::do_try_state(), "player is playing a game without a seat");
+	});
+
+	new_test_ext().execute_with(|| {
+		let _ = <Test as Config>::Currency::deposit_creating(&ALICE, 100_000_000);
+		assert_ok!(PalletGame::open_and_join(
+			Origin::signed(ALICE),
+			10_000,
+			GameSettings::default(),
+			None
+		));
+		let game_id = PalletGame::open_games(0)[0];
+		assert_ok!(PalletGame::do_try_state());
+		crate::Games::<Test>::mutate(game_id, |game| {
+			game.as_mut().unwrap().status = GameStatus::Start;
+		});
+		assert_err!(PalletGame::do_try_state(), "started game is not listed");
+	});
+}
+
+/// One step of a random session against the mock runtime.
+#[derive(Clone, Debug)]
+enum Action {
+	Open { who: usize, ticket: u64, settings: GameSettings, commit: Option<u8> },
+	Join { who: usize, game: usize, commit: Option<u8> },
+	Left(usize),
+	Cancel(usize),
+	Start(usize),
+	Play { who: usize, x: u32, y: u32 },
+	ChooseColor(usize, Color),
+	Reveal(usize, u8),
+	OfferDraw(usize),
+	AcceptDraw(usize),
+	ClaimTimeout(usize),
+	SetBoardLimits(BoardLimits),
+	SetHouseRake(u32),
+	Advance(u64),
+}
+
+const FUZZ_PLAYERS: [AccountId32; 4] = [ALICE, BOB, CHARLIE, AccountId32::new([4u8; 32])];
+
+fn any_settings() -> impl Strategy<Value = GameSettings> {
+	let rule = prop_oneof![
+		Just(RuleSet::Freestyle),
+		Just(RuleSet::Standard),
+		Just(RuleSet::Caro),
+		Just(RuleSet::Renju),
+	];
+	let opening = prop_oneof![
+		Just(OpeningRule::Free),
+		Just(OpeningRule::Pro),
+		Just(OpeningRule::Swap),
+		Just(OpeningRule::Swap2),
+	];
+	// mostly seat counts a game can have, sometimes out of bounds
+	let players = prop_oneof![4 => 2u8..=4, 1 => 0u8..=6];
+	(0u8..=25, 0u8..=25, 0u8..=7, rule, opening, any::<bool>(), players).prop_map(
+		|(width, height, win_length, rule, opening, commit_reveal, players)| GameSettings {
+			width,
+			height,
+			win_length,
+			rule,
+			opening,
+			commit_reveal,
+			players,
+		},
+	)
+}
+
+fn any_action() -> impl Strategy<Value = Action> {
+	let who = 0..FUZZ_PLAYERS.len();
+	let commit = proptest::option::of(0u8..3);
+	// mostly moves on the board, sometimes far off it
+	let coordinate = prop_oneof![8 => 0u32..16, 1 => 16u32..200, 1 => any::<u32>()];
+	let color = prop_oneof![Just(Color::Black), Just(Color::White)];
+	let limits = (0u8..=25, 0u8..=25, 0u8..=7, 0u8..=7).prop_map(
+		|(min_size, max_size, min_win_length, max_win_length)| BoardLimits {
+			min_size,
+			max_size,
+			min_win_length,
+			max_win_length,
+		},
+	);
+	prop_oneof![
+		3 => (who.clone(), 0u64..20_000, any_settings(), commit.clone())
+			.prop_map(|(who, ticket, settings, commit)| Action::Open { who, ticket, settings, commit }),
+		4 => (who.clone(), 0usize..4, commit)
+			.prop_map(|(who, game, commit)| Action::Join { who, game, commit }),
+		1 => who.clone().prop_map(Action::Left),
+		1 => who.clone().prop_map(Action::Cancel),
+		3 => who.clone().prop_map(Action::Start),
+		12 => (who.clone(), coordinate.clone(), coordinate)
+			.prop_map(|(who, x, y)| Action::Play { who, x, y }),
+		1 => (who.clone(), color).prop_map(|(who, color)| Action::ChooseColor(who, color)),
+		2 => (who.clone(), 0u8..3).prop_map(|(who, secret)| Action::Reveal(who, secret)),
+		1 => who.clone().prop_map(Action::OfferDraw),
+		1 => who.clone().prop_map(Action::AcceptDraw),
+		1 => who.prop_map(Action::ClaimTimeout),
+		1 => limits.prop_map(Action::SetBoardLimits),
+		1 => (0u32..=100).prop_map(Action::SetHouseRake),
+		2 => (1u64..15).prop_map(Action::Advance),
+	]
+}
+
+fn fuzz_commitment(who: &AccountId32, commit: Option<u8>) -> Option<sp_core::H256> {
+	commit.map(|secret| PalletGame::commitment_of(who, &[secret; 32]))
+}
+
+/// Dispatch `action`, errors are expected and ignored, only panics fail the run.
+fn dispatch(action: Action) {
+	let signed = |who: usize| Origin::signed(FUZZ_PLAYERS[who].clone());
+	let _ = match action {
+		Action::Open { who, ticket, settings, commit } => PalletGame::open_and_join(
+			signed(who),
+			ticket,
+			settings,
+			fuzz_commitment(&FUZZ_PLAYERS[who], commit),
+		),
+		Action::Join { who, game, commit } => {
+			// a listed game when there is one, otherwise an id nobody opened
+			let game_id = PalletGame::open_games(0).get(game).copied().unwrap_or([game as u8; 32]);
+			PalletGame::join(signed(who), game_id, fuzz_commitment(&FUZZ_PLAYERS[who], commit))
+		},
+		Action::Left(who) => PalletGame::left(signed(who)),
+		Action::Cancel(who) => PalletGame::cancel(signed(who)),
+		Action::Start(who) => PalletGame::start(signed(who)),
+		Action::Play { who, x, y } => PalletGame::play(signed(who), x, y),
+		Action::ChooseColor(who, color) => PalletGame::choose_color(signed(who), color),
+		Action::Reveal(who, secret) => PalletGame::reveal(signed(who), [secret; 32]),
+		Action::OfferDraw(who) => PalletGame::offer_draw(signed(who)),
+		Action::AcceptDraw(who) => PalletGame::accept_draw(signed(who)),
+		Action::ClaimTimeout(who) => PalletGame::claim_timeout(signed(who)),
+		Action::SetBoardLimits(limits) => PalletGame::set_board_limits(Origin::root(), limits),
+		Action::SetHouseRake(percent) =>
+			PalletGame::set_house_rake(Origin::root(), Perbill::from_percent(percent)),
+		Action::Advance(blocks) => {
+			run_to_block(System::block_number() + blocks);
+			Ok(())
+		},
+	};
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig::with_cases(64))]
+
+	#[test]
+	fn random_extrinsics_should_not_panic(
+		actions in proptest::collection::vec(any_action(), 1..80),
+	) {
+		ExtBuilder.build_and_execute(|| {
+			for who in FUZZ_PLAYERS.iter() {
+				let _ = <Test as Config>::Currency::deposit_creating(who, 1_000_000_000);
+			}
+			for action in actions {
+				dispatch(action);
+			}
+			run_to_block(System::block_number() + 120);
+		});
+	}
+}
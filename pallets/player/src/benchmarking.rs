@@ -0,0 +1,19 @@
+//! Benchmarking setup for pallet-player
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Player;
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+benchmarks! {
+	create_player {
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller.clone()), [1u8; 16])
+	verify {
+		assert!(Player::<T>::player_owned(&caller).is_some());
+	}
+
+	impl_benchmark_test_suite!(Player, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -2,12 +2,17 @@
 
 pub use pallet::*;
 
+pub mod weights;
+
 #[cfg(test)]
 mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
@@ -22,6 +27,8 @@ pub mod pallet {
 	use frame_system::pallet_prelude::*;
 	use sp_io::hashing::blake2_256;
 
+	use crate::weights::WeightInfo;
+
 	#[cfg(feature = "std")]
 	use frame_support::serde::{Deserialize, Serialize};
 
@@ -53,6 +60,9 @@ pub mod pallet {
 		type Currency: Currency<Self::AccountId>;
 
 		type GameRandomness: Randomness<Self::Hash, Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	// Errors.
@@ -78,10 +88,9 @@ pub mod pallet {
 	#[pallet::getter(fn player_owned)]
 	pub type PlayerOwned<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, ID>;
 
-
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::weight(100)]
+		#[pallet::weight(T::WeightInfo::create_player())]
 		pub fn create_player(origin: OriginFor<T>, name: NAME) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			let id = Self::create_new_player(sender.clone(), name)?;
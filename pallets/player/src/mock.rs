@@ -86,6 +86,7 @@ impl pallet_player::Config for Test {
 	type Event = Event;
 	type Currency = Balances;
 	type GameRandomness = RandomnessCollectiveFlip;
+	type WeightInfo = ();
 }
 
 // Build genesis storage according to the mock runtime.
@@ -0,0 +1,38 @@
+//! Weights for pallet_player
+//!
+//! THESE WEIGHTS ARE HAND-ESTIMATED from the storage accesses of each benchmark path and
+//! have not been measured yet. Replace this file with the generated one, built from the
+//! benchmarks on reference hardware, before relying on it:
+//!
+//! ./scripts/benchmark.sh player
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use frame_support::sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_player.
+pub trait WeightInfo {
+	fn create_player() -> Weight;
+}
+
+/// Weights for pallet_player using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn create_player() -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_player() -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+}
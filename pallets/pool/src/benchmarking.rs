@@ -0,0 +1,61 @@
+//! Benchmarking setup for pallet-pool
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Pool;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const MARK_BLOCK: u64 = 30;
+
+fn funded<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 1_000u32.into());
+}
+
+fn setup_pool<T: Config>() {
+	<MaxPlayer<T>>::put(u32::MAX);
+	<MarkBlock<T>>::put(MARK_BLOCK);
+	<PoolFee<T>>::put(T::Currency::minimum_balance());
+}
+
+fn accounts<T: Config>(n: u32) -> Vec<T::AccountId> {
+	(0..n).map(|i| account("player", i, SEED)).collect()
+}
+
+benchmarks! {
+	// the new player is pushed behind `n` others
+	join {
+		let n in 0 .. T::MaxNewPlayer::get() - 1;
+		setup_pool::<T>();
+		let others: BoundedVec<_, _> = accounts::<T>(n).try_into().unwrap();
+		<NewPlayers<T>>::put(others);
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(Pool::<T>::players(&caller).is_some());
+	}
+
+	// an in-game player found last among `n` others
+	leave {
+		let n in 0 .. T::MaxIngamePlayer::get() - 1;
+		setup_pool::<T>();
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		Pool::<T>::join(RawOrigin::Signed(caller.clone()).into())?;
+		<NewPlayers<T>>::kill();
+		let mut ingame = accounts::<T>(n);
+		ingame.push(caller.clone());
+		let ingame: BoundedVec<_, _> = ingame.try_into().unwrap();
+		<IngamePlayers<T>>::put(ingame);
+		let block = Pool::<T>::u64_to_block(MARK_BLOCK + 1).unwrap();
+		frame_system::Pallet::<T>::set_block_number(block);
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(Pool::<T>::players(&caller).is_none());
+	}
+
+	impl_benchmark_test_suite!(Pool, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -25,17 +25,23 @@ use frame_support::traits::GenesisBuild;
 pub use pallet::*;
 pub use pallet_player::PlayerOwned;
 
+pub mod weights;
+
 #[cfg(test)]
 mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
+	use crate::weights::WeightInfo;
 
-	type BalanceOf<T> =
+	pub type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 	type ID = [u8; 32];
 
@@ -64,6 +70,9 @@ pub mod pallet {
 
 		#[pallet::constant]
 		type MaxIngamePlayer: Get<u32>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	// Errors.
@@ -104,6 +113,11 @@ pub mod pallet {
 				}
 			}
 		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
 	}
 
 	#[pallet::storage]
@@ -162,7 +176,7 @@ pub mod pallet {
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::weight(100)]
+		#[pallet::weight(T::WeightInfo::join(T::MaxNewPlayer::get()))]
 		pub fn join(origin: OriginFor<T>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			Self::join_pool(sender.clone())?;
@@ -173,7 +187,10 @@ pub mod pallet {
 			Ok(())
 		}
 
-		#[pallet::weight(100)]
+		// the leaving player is looked for in both lists
+		#[pallet::weight(T::WeightInfo::leave(
+			T::MaxNewPlayer::get().saturating_add(T::MaxIngamePlayer::get())
+		))]
 		pub fn leave(origin: OriginFor<T>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			Self::leave_pool(&sender)?;
@@ -226,25 +243,14 @@ pub mod pallet {
 				let range_block = block_number - join_block;
 
 				if range_block < Self::mark_block() {
-					<NewPlayers<T>>::try_mutate(|players| {
-						if let Some(ind) = players.iter().position(|id| id == sender) {
-							players.swap_remove(ind);
-						}
-						Ok(())
-					})
-					.map_err(|_: Error<T>| <Error<T>>::PlayerNotFound)?;
 					refund_fee = Self::pool_fee();
 				} else {
-					<IngamePlayers<T>>::try_mutate(|players| {
-						if let Some(ind) = players.iter().position(|id| id == sender) {
-							players.swap_remove(ind);
-						}
-						Ok(())
-					})
-					.map_err(|_: Error<T>| <Error<T>>::PlayerNotFound)?;
 					refund_fee = Self::calculate_ingame_refund_amount(join_block, block_number)?;
 				}
+				// a player who joined less than a mark ago may already be in game
+				Self::remove_from_lists(sender);
 				<Players<T>>::remove(sender);
+				<PlayerCount<T>>::mutate(|count| *count = count.saturating_sub(1));
 				let _ = T::Currency::deposit_into_existing(sender, refund_fee);
 			} else {
 				return Err(<Error<T>>::PlayerNotFound);
@@ -271,6 +277,7 @@ pub mod pallet {
 		 */
 		fn kick_ingame_player(player: &T::AccountId) -> Result<(), Error<T>> {
 			<Players<T>>::remove(player);
+			<PlayerCount<T>>::mutate(|count| *count = count.saturating_sub(1));
 			<IngamePlayers<T>>::try_mutate(|players| {
 				if let Some(ind) = players.iter().position(|id| id == player) {
 					players.swap_remove(ind);
@@ -281,6 +288,12 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Take `player` out of NewPlayers or IngamePlayers, whichever holds them.
+		fn remove_from_lists(player: &T::AccountId) {
+			<NewPlayers<T>>::mutate(|players| players.retain(|id| id != player));
+			<IngamePlayers<T>>::mutate(|players| players.retain(|id| id != player));
+		}
+
 		fn charge_ingame() -> Result<(), Error<T>> {
 			let ingame_players: Vec<T::AccountId> = Self::ingame_players().into_inner();
 			for player in ingame_players {
@@ -331,6 +344,33 @@ pub mod pallet {
 			return 0u64;
 		}
 	}
+
+	// Storage invariants, checked after every test and on runtime upgrades under try-runtime.
+	#[cfg(any(test, feature = "try-runtime"))]
+	impl<T: Config> Pallet<T> {
+		/// Every player is in exactly one of NewPlayers and IngamePlayers, and PlayerCount
+		/// counts them.
+		pub fn do_try_state() -> Result<(), &'static str> {
+			let members = <Players<T>>::iter_keys().count();
+			ensure!(
+				Self::player_count() as usize == members,
+				"player count does not match the pool members"
+			);
+			let new_players = Self::new_players();
+			let ingame_players = Self::ingame_players();
+			ensure!(
+				new_players.len() + ingame_players.len() == members,
+				"pool member missing from the player lists"
+			);
+			for player in new_players.iter().chain(ingame_players.iter()) {
+				ensure!(<Players<T>>::contains_key(player), "listed player is not a pool member");
+			}
+			for player in new_players.iter() {
+				ensure!(!ingame_players.contains(player), "player is both new and in game");
+			}
+			Ok(())
+		}
+	}
 }
 
 #[cfg(feature = "std")]
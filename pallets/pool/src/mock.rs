@@ -100,6 +100,7 @@ impl pallet_pool::Config for Test {
 	type Currency = Balances;
 	type MaxNewPlayer = MaxNewPlayer;
 	type MaxIngamePlayer = MaxIngamePlayer;
+	type WeightInfo = ();
 }
 
 // Build genesis storage according to the mock runtime.
@@ -158,6 +159,7 @@ impl ExtBuilder {
 	pub fn build_and_execute(self, test: impl FnOnce() -> ()) {
 		let mut ext = self.build();
 		ext.execute_with(test);
+		ext.execute_with(|| PalletPool::do_try_state().unwrap());
 		ext.execute_with(|| System::set_block_number(1));
 	}
 }
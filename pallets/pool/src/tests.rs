@@ -1,5 +1,5 @@
 use crate::{mock::*, Config, Error};
-use frame_support::{assert_err, assert_ok, traits::Currency};
+use frame_support::{assert_err, assert_ok, traits::Currency, BoundedVec};
 
 const POOL_FEE: u64 = 10000000000000000;
 const MARK_BLOCK: u64 = 30;
@@ -155,3 +155,31 @@ fn leave_pool_should_fail() {
 		assert_err!(PalletPool::leave(Origin::signed(ALICE)), <Error<Test>>::PlayerNotFound);
 	})
 }
+
+#[test]
+fn leave_pool_after_moving_in_game_should_work() {
+	ExtBuilder::default().build_and_execute(|| {
+		run_to_block(MARK_BLOCK - 1);
+		assert_ok!(PalletPool::join(Origin::signed(ALICE)));
+		run_to_block(MARK_BLOCK + 5);
+		assert_eq!(PalletPool::ingame_players().len(), 1, "player should be in game");
+
+		assert_ok!(PalletPool::leave(Origin::signed(ALICE)));
+		assert_eq!(PalletPool::ingame_players().len(), 0, "player should leave the game");
+		assert_eq!(PalletPool::player_count(), 0, "player count not correct");
+	})
+}
+
+#[test]
+fn try_state_should_catch_corrupted_storage() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PalletPool::do_try_state());
+		crate::PlayerCount::<Test>::put(1);
+		assert_err!(PalletPool::do_try_state(), "player count does not match the pool members");
+	});
+
+	new_test_ext().execute_with(|| {
+		crate::IngamePlayers::<Test>::put(BoundedVec::try_from(vec![ALICE]).unwrap());
+		assert_err!(PalletPool::do_try_state(), "pool member missing from the player lists");
+	});
+}
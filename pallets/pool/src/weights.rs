@@ -0,0 +1,53 @@
+//! Weights for pallet_pool
+//!
+//! THESE WEIGHTS ARE HAND-ESTIMATED from the storage accesses of each benchmark path and
+//! have not been measured yet. Replace this file with the generated one, built from the
+//! benchmarks on reference hardware, before relying on it:
+//!
+//! ./scripts/benchmark.sh pool
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use frame_support::sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_pool.
+pub trait WeightInfo {
+	fn join(n: u32, ) -> Weight;
+	fn leave(n: u32, ) -> Weight;
+}
+
+/// Weights for pallet_pool using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn join(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((60_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn leave(n: u32, ) -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add((70_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn join(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((60_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	fn leave(n: u32, ) -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add((70_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+}
@@ -31,7 +31,7 @@ pub use frame_support::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -100,7 +100,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -277,30 +277,36 @@ impl pallet_sudo::Config for Runtime {
 }
 
 parameter_types! {
-	pub const MaxGomokuPlayer: u32 = 2;
+	pub const MaxGomokuPlayer: u32 = 4;
 	pub const MaxGame: u32 = 10;
-	pub const MaxOpenGame: u32 = 10;
-	pub const MaxStartGame: u32 = 10;
 	pub const OpenGameFee: u32 = 1000000000u32;
-	pub const MaxEndedGame: u32 = 1000000000u32;
+	pub const EndedGameRetention: BlockNumber = 7 * DAYS;
+	pub const MaxMoves: u32 = 361;
+	pub const GomokuPalletId: PalletId = PalletId(*b"py/gomok");
+	pub const MoveTimeout: BlockNumber = 5 * MINUTES;
+	pub const OpenGameTimeout: BlockNumber = HOURS;
 }
 
 impl pallet_gomoku::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	type PalletId = GomokuPalletId;
 	type MaxGomokuPlayer = MaxGomokuPlayer;
 	type MaxGame = MaxGame;
-	type MaxOpenGame = MaxOpenGame;
-	type MaxStartGame = MaxStartGame;
 	type OpenGameFee = OpenGameFee;
-	type MaxEndedGame = MaxEndedGame;
+	type EndedGameRetention = EndedGameRetention;
+	type MaxMoves = MaxMoves;
 	type GameRandomness = RandomnessCollectiveFlip;
+	type MoveTimeout = MoveTimeout;
+	type OpenGameTimeout = OpenGameTimeout;
+	type WeightInfo = pallet_gomoku::weights::SubstrateWeight<Runtime>;
 }
 
 impl pallet_player::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type GameRandomness = RandomnessCollectiveFlip;
+	type WeightInfo = pallet_player::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -313,6 +319,7 @@ impl pallet_pool::Config for Runtime {
 	type Currency = Balances;
 	type MaxNewPlayer = MaxNewPlayer;
 	type MaxIngamePlayer = MaxIngamePlayer;
+	type WeightInfo = pallet_pool::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -495,6 +502,46 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_gomoku_rpc_runtime_api::GomokuApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn open_games(page: u32) -> Vec<pallet_gomoku::GameInfo<AccountId, Balance, BlockNumber>> {
+			Gomoku::open_game_infos(page)
+		}
+
+		fn game(game_id: [u8; 32]) -> Option<pallet_gomoku::GameInfo<AccountId, Balance, BlockNumber>> {
+			Gomoku::game_info(&game_id)
+		}
+
+		fn board(game_id: [u8; 32]) -> Option<pallet_gomoku::board::Board> {
+			Gomoku::board_of(&game_id)
+		}
+
+		fn current_turn(game_id: [u8; 32]) -> Option<(AccountId, BlockNumber)> {
+			Gomoku::current_turn(&game_id)
+		}
+
+		fn game_of(account: AccountId) -> Option<[u8; 32]> {
+			Gomoku::game_of(&account)
+		}
+
+		fn ended_games(page: u32) -> Vec<pallet_gomoku::EndedGameInfo<AccountId, Balance, BlockNumber>> {
+			Gomoku::ended_game_infos(page)
+		}
+	}
+
+	#[cfg(feature = "try-runtime")]
+	impl frame_try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade() -> (Weight, Weight) {
+			// the pallets' post_upgrade checks run their try_state invariants, stop right here
+			// with a backtrace if any of them fails
+			let weight = Executive::try_runtime_upgrade().unwrap();
+			(weight, BlockWeights::get().max_block)
+		}
+
+		fn execute_block_no_check(block: Block) -> Weight {
+			Executive::execute_block_no_check(block)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -512,7 +559,9 @@ impl_runtime_apis! {
 			list_benchmark!(list, extra, frame_system, SystemBench::<Runtime>);
 			list_benchmark!(list, extra, pallet_balances, Balances);
 			list_benchmark!(list, extra, pallet_timestamp, Timestamp);
-			list_benchmark!(list, extra, pallet_player, TemplateModule);
+			list_benchmark!(list, extra, pallet_gomoku, Gomoku);
+			list_benchmark!(list, extra, pallet_player, Game);
+			list_benchmark!(list, extra, pallet_pool, Pool);
 
 			let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -550,7 +599,9 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
-			add_benchmark!(params, batches, pallet_player, TemplateModule);
+			add_benchmark!(params, batches, pallet_gomoku, Gomoku);
+			add_benchmark!(params, batches, pallet_player, Game);
+			add_benchmark!(params, batches, pallet_pool, Pool);
 
 			Ok(batches)
 		}
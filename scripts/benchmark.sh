@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+# This script is meant to be run on Unix/Linux based systems
+set -e
+
+echo "*** Generating pallet weights from the benchmarks"
+
+cd $(dirname ${BASH_SOURCE[0]})/..
+
+cargo build --release -p node-template --features runtime-benchmarks
+
+for pallet in ${@:-gomoku player pool}; do
+	./target/release/node-template benchmark \
+		--chain=dev \
+		--execution=wasm \
+		--wasm-execution=compiled \
+		--pallet=pallet_$pallet \
+		--extrinsic='*' \
+		--steps=50 \
+		--repeat=20 \
+		--template=./.maintain/frame-weight-template.hbs \
+		--output=./pallets/$pallet/src/weights.rs
+done